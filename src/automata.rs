@@ -1,19 +1,92 @@
+pub mod error;
 pub mod state;
 pub mod transition;
 
+use crate::parser::dot::DotParser;
+use crate::parser::{escape_symbol, AutomataParser, ParseError};
+use crate::regex::Expr;
+use error::AutomataError;
 use state::{Set, State};
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::ops::Index;
+use std::sync::Arc;
 use transition::Transition;
 
 #[derive(Debug, Clone, Default)]
 pub struct Automata {
-    alphabet: Vec<String>,
+    // shared with the automata derived from this one until either changes it
+    alphabet: Arc<Vec<String>>,
     states: Vec<State>,
+    state_indices: HashMap<State, usize>,
     entry_state: usize,
     accept_states: Vec<usize>,
     transitions: Vec<Vec<Transition>>,
+    default_transitions: Vec<Option<usize>>,
+    // what the determinization recorded about the result, it's only kept as
+    // long as the states are; `merge` and `relabel_with` drop it
+    provenance: Vec<Set<String>>,
+    accept_priorities: Vec<Option<usize>>,
+    subset_indices: BTreeMap<Vec<usize>, usize>,
+}
+
+/// Work done by the subset construction: DFA states created (the trap state
+/// excluded), lookups of candidate subsets among the known ones and the
+/// largest number of states waiting to be processed at once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeterminizeStats {
+    pub states_created: usize,
+    pub membership_checks: usize,
+    pub max_frontier: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Acceptance {
+    Accepted,
+    Rejected,
+    InvalidInput { position: usize, reason: String },
+}
+
+/// Words of a labeled dataset the automaton classified wrongly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassifyReport {
+    pub false_accepts: Vec<String>,
+    pub false_rejects: Vec<String>,
+}
+
+/// What `to_deterministic_with` does with the transitions the subset
+/// construction leaves out: keep the DFA partial, or send them to a trap state
+/// tagged `"!"` or with the given name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TrapPolicy {
+    None,
+    #[default]
+    Unnamed,
+    Named(String),
+}
+
+impl ClassifyReport {
+    pub fn passed(&self) -> bool {
+        self.false_accepts.is_empty() && self.false_rejects.is_empty()
+    }
+}
+
+/// How `to_dot_with` draws the automaton. With `subset_labels` (the default,
+/// used by `to_dot`) every node is labeled with its tags as given by
+/// `State::label` (for a determinized automaton, the subset of NFA states it
+/// stands for), otherwise just with its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DotOptions {
+    pub subset_labels: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions {
+            subset_labels: true,
+        }
+    }
 }
 
 pub trait AutomataIndex {
@@ -45,6 +118,10 @@ impl Automata {
         Default::default()
     }
 
+    pub fn from_dot(source: &str) -> Result<Self, ParseError> {
+        DotParser::with_source(source).parse()
+    }
+
     pub fn len(&self) -> usize {
         self.states.len()
     }
@@ -53,31 +130,112 @@ impl Automata {
         self.states.is_empty()
     }
 
+    /// Index of the first state with exactly these tags.
     pub fn find(&self, state: &State) -> Option<usize> {
-        for (index, s) in self.states.iter().enumerate() {
-            if s == state {
-                return Some(index);
-            }
-        }
-
-        None
+        self.state_indices.get(state).copied()
     }
 
     pub fn push_state(&mut self, state: State) {
+        let index = self.len();
+        self.state_indices.entry(state.clone()).or_insert(index);
         self.states.push(state);
         self.transitions.push(vec![]);
+        self.default_transitions.push(None);
     }
 
     pub fn push_symbol(&mut self, symbol: &str) {
-        self.alphabet.push(symbol.to_owned());
+        Arc::make_mut(&mut self.alphabet).push(symbol.to_owned());
+    }
+
+    pub fn insert_alphabet_symbol_at(
+        &mut self,
+        index: usize,
+        symbol: &str,
+    ) -> Result<(), AutomataError> {
+        if index > self.alphabet.len() {
+            return Err(AutomataError::IndexOutOfBounds {
+                index,
+                len: self.alphabet.len(),
+            });
+        }
+
+        check_symbol(symbol)?;
+        if self.alphabet.iter().any(|s| s == symbol) {
+            return Err(AutomataError::DuplicateSymbol(symbol.to_owned()));
+        }
+
+        Arc::make_mut(&mut self.alphabet).insert(index, symbol.to_owned());
+        Ok(())
+    }
+
+    /// Checks that no alphabet symbol is one of the epsilon representations
+    /// (`""` or `"-1"`) and that every state index used is in bounds.
+    pub fn validate(&self) -> Result<(), AutomataError> {
+        for symbol in self.alphabet.iter() {
+            check_symbol(symbol)?;
+        }
+
+        let len = self.len();
+        let check_index = |index: usize| {
+            if index < len {
+                Ok(())
+            } else {
+                Err(AutomataError::IndexOutOfBounds { index, len })
+            }
+        };
+
+        if !self.is_empty() {
+            check_index(self.entry_state)?;
+        }
+
+        for &index in &self.accept_states {
+            check_index(index)?;
+        }
+
+        for (state_transition, default_transition) in
+            self.transitions.iter().zip(&self.default_transitions)
+        {
+            for transition in state_transition {
+                check_index(transition.end_state())?;
+            }
+
+            if let Some(end_state) = default_transition {
+                check_index(*end_state)?;
+            }
+        }
+
+        Ok(())
     }
 
     pub fn push_accept_state(&mut self, index: impl AutomataIndex) {
         self.accept_states.push(index.index(self));
     }
 
+    /// Sorts the alphabet lexicographically. Transitions refer to symbols by
+    /// value, so this only changes the order symbols are listed and iterated
+    /// in (e.g. the output column order), never the language.
+    pub fn symbol_sort(&mut self) {
+        Arc::make_mut(&mut self.alphabet).sort();
+    }
+
+    pub fn accept_when(&mut self, predicate: impl Fn(&State) -> bool) {
+        for index in 0..self.len() {
+            if predicate(&self.states[index]) && !self.accept_states.contains(&index) {
+                self.accept_states.push(index);
+            }
+        }
+    }
+
+    pub fn accept_by_tag_suffix(&mut self, suffix: &str) {
+        self.accept_when(|state| state.len() == 1 && state.label().ends_with(suffix));
+    }
+
+    pub fn accept_by_tag_prefix(&mut self, prefix: &str) {
+        self.accept_when(|state| state.len() == 1 && state.label().starts_with(prefix));
+    }
+
     pub fn push_transition_from(&mut self, index: impl AutomataIndex, transition: Transition) {
-        let index = index.index(&self);
+        let index = index.index(self);
         if !self.transitions[index].contains(&transition) {
             self.transitions[index].push(transition);
         }
@@ -87,40 +245,201 @@ impl Automata {
         self.entry_state = index.index(self);
     }
 
+    /// Drops epsilon transitions from a state to itself, they never change the
+    /// language nor any epsilon closure.
+    pub fn remove_epsilon_self_loops(&mut self) {
+        for (index, state_transition) in self.transitions.iter_mut().enumerate() {
+            state_transition.retain(|t| !(t.is_epsilon() && t.end_state() == index));
+        }
+    }
+
     pub fn transitions_from(&self, index: impl AutomataIndex) -> &Vec<Transition> {
         &self.transitions[index.index(self)]
     }
 
+    pub fn transition_relation(&self) -> BTreeSet<(usize, String, usize)> {
+        let mut relation = BTreeSet::new();
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            for transition in state_transition {
+                relation.insert((
+                    index,
+                    transition.symbol().to_owned(),
+                    transition.end_state(),
+                ));
+            }
+        }
+
+        relation
+    }
+
+    /// Sets the transition taken from `index` on any non-epsilon symbol that
+    /// has no explicit transition, which lets open alphabets stay total.
+    pub fn set_default_transition(&mut self, index: impl AutomataIndex, end_state: usize) {
+        let index = index.index(self);
+        self.default_transitions[index] = Some(end_state);
+    }
+
+    pub fn default_transition(&self, index: impl AutomataIndex) -> Option<usize> {
+        self.default_transitions[index.index(self)]
+    }
+
+    /// Makes the automaton total over any input by adding a trap state and a
+    /// default transition into it for every state without one.
+    pub fn complete_with_default_trap(&mut self) {
+        self.push_state("!".into());
+        let never_state_index = self.len() - 1;
+        for default_transition in &mut self.default_transitions {
+            if default_transition.is_none() {
+                *default_transition = Some(never_state_index);
+            }
+        }
+    }
+
     pub fn move_from_with(&self, index: impl AutomataIndex, symbol: &str) -> Vec<usize> {
+        let index = index.index(self);
         let mut transitions = Vec::new();
 
-        for transition in self.transitions_from(index.index(self)) {
+        for transition in self.transitions_from(index) {
             if transition.symbol() == symbol {
                 transitions.push(transition.end_state());
             }
         }
 
+        if transitions.is_empty() && !symbol.is_empty() {
+            if let Some(end_state) = self.default_transitions[index] {
+                transitions.push(end_state);
+            }
+        }
+
         transitions
     }
 
-    pub fn is_deterministic(&self) -> bool {
-        for state_transition in &self.transitions {
-            if state_transition.len() != self.alphabet.len() {
-                return false;
+    pub fn accept_states_tags(&self) -> Vec<&Set<String>> {
+        self.accept_states
+            .iter()
+            .map(|&index| self[index].tags())
+            .collect()
+    }
+
+    pub fn reachable_states(&self) -> Vec<usize> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; self.len()];
+        let mut stack = vec![self.entry_state];
+        visited[self.entry_state] = true;
+
+        while let Some(state) = stack.pop() {
+            let end_states = self.transitions_from(state).iter().map(|t| t.end_state());
+            for end_state in end_states.chain(self.default_transitions[state]) {
+                if !visited[end_state] {
+                    visited[end_state] = true;
+                    stack.push(end_state);
+                }
             }
+        }
 
-            let mut found_symbols = HashSet::new();
-            for transition in state_transition {
-                // found a repeated symbol, it's not deterministic
-                if found_symbols.contains(transition.symbol()) {
-                    return false;
+        (0..self.len()).filter(|&index| visited[index]).collect()
+    }
+
+    /// Whether no word is accepted: no accept state can be reached from the
+    /// entry, unlike `is_empty`, which tells whether there are no states.
+    pub fn is_empty_language(&self) -> bool {
+        self.reachable_accept_states().is_empty()
+    }
+
+    pub fn reachable_accept_states(&self) -> Vec<usize> {
+        let reachable = self.reachable_states();
+        self.accept_states
+            .iter()
+            .cloned()
+            .filter(|state| reachable.contains(state))
+            .collect()
+    }
+
+    /// States from which no accept state can be reached.
+    pub fn dead_states(&self) -> Vec<usize> {
+        let mut predecessors = vec![Vec::new(); self.len()];
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            let end_states = state_transition.iter().map(|t| t.end_state());
+            for end_state in end_states.chain(self.default_transitions[index]) {
+                predecessors[end_state].push(index);
+            }
+        }
+
+        let mut alive = vec![false; self.len()];
+        let mut stack = self.accept_states.clone();
+        for &state in &stack {
+            alive[state] = true;
+        }
+
+        while let Some(state) = stack.pop() {
+            for &predecessor in &predecessors[state] {
+                if !alive[predecessor] {
+                    alive[predecessor] = true;
+                    stack.push(predecessor);
                 }
+            }
+        }
+
+        (0..self.len()).filter(|&index| !alive[index]).collect()
+    }
+
+    /// Drops every transition leading into a dead state, keeping all the states
+    /// (and so their indices) in place.
+    pub fn trim_transitions_to_dead(&mut self) {
+        let mut dead = vec![false; self.len()];
+        for index in self.dead_states() {
+            dead[index] = true;
+        }
+
+        for state_transition in &mut self.transitions {
+            state_transition.retain(|t| !dead[t.end_state()]);
+        }
 
-                found_symbols.insert(transition.symbol());
+        for default_transition in &mut self.default_transitions {
+            if default_transition.is_some_and(|end_state| dead[end_state]) {
+                *default_transition = None;
             }
         }
+    }
 
-        true
+    pub fn is_trim(&self) -> bool {
+        self.reachable_states().len() == self.len() && self.dead_states().is_empty()
+    }
+
+    /// No epsilon transitions and at most one transition per symbol from each
+    /// state, missing transitions are fine (see `is_complete`).
+    pub fn is_deterministic(&self) -> bool {
+        self.transitions.iter().all(|state_transition| {
+            let mut found_symbols = HashSet::new();
+            state_transition
+                .iter()
+                .all(|t| !t.is_epsilon() && found_symbols.insert(t.symbol()))
+        })
+    }
+
+    pub fn has_epsilon(&self) -> bool {
+        self.transitions
+            .iter()
+            .flatten()
+            .any(Transition::is_epsilon)
+    }
+
+    /// Whether some state has an epsilon transition or more than one
+    /// transition on the same symbol.
+    pub fn is_nfa(&self) -> bool {
+        self.has_epsilon() || !self.is_deterministic()
+    }
+
+    /// Every state has a transition on every symbol of the alphabet.
+    pub fn is_complete(&self) -> bool {
+        self.transitions.iter().all(|state_transition| {
+            self.alphabet
+                .iter()
+                .all(|symbol| state_transition.iter().any(|t| t.symbol() == symbol))
+        })
     }
 
     pub fn e_closure_set(&self, input: &[impl AutomataIndex]) -> Vec<usize> {
@@ -144,170 +463,4850 @@ impl Automata {
         e_closure_set
     }
 
-    pub fn to_deterministic(&self) -> Self {
-        let e_closure_set = self.e_closure_set(&[self.entry_state]);
-        let state = {
-            let mut set = Set::new();
-            for state in e_closure_set {
-                set.append(&mut self[state].tags().clone());
-            }
+    pub fn accepts(&self, input: &[&str]) -> bool {
+        self.accepts_nfa_bounded(input, usize::MAX).unwrap()
+    }
 
-            State::from(set)
+    /// Like `accepts`, but fails as soon as the simulation has to keep track of
+    /// more than `max_set_size` states at once, bounding the cost of each step.
+    pub fn accepts_nfa_bounded(
+        &self,
+        input: &[&str],
+        max_set_size: usize,
+    ) -> Result<bool, AutomataError> {
+        // no symbol to read, no use for the dead states
+        let dead = if input.is_empty() {
+            Vec::new()
+        } else {
+            self.dead_mask()
         };
 
-        let mut afd_automata = Automata::new();
-        afd_automata.push_state(state);
-        afd_automata.alphabet = self.alphabet.clone();
+        let (accepted, _) = self.accepts_consumed(input, max_set_size, &dead)?;
+        Ok(accepted)
+    }
+
+    /// Runs `accepts_nfa_bounded`, also returning how many symbols were read
+    /// before the answer was known: only dead states left means the input is
+    /// rejected, as is any input when there are no symbols to read at all
+    /// (an empty alphabet and no default transitions). `dead` is given by
+    /// `dead_mask`, so that it's computed once for many inputs.
+    fn accepts_consumed(
+        &self,
+        input: &[&str],
+        max_set_size: usize,
+        dead: &[bool],
+    ) -> Result<(bool, usize), AutomataError> {
+        if self.is_empty() {
+            return Ok((false, 0));
+        }
+
+        let check_size = |current: &[usize]| match current.len() {
+            size if size > max_set_size => Err(AutomataError::StateSetTooLarge {
+                size,
+                limit: max_set_size,
+            }),
+            _ => Ok(()),
+        };
+
+        let mut current = self.e_closure_set(&[self.entry_state]);
+        check_size(&current)?;
+        let open_alphabet = self.default_transitions.iter().any(Option::is_some);
+        if !input.is_empty() && self.alphabet.is_empty() && !open_alphabet {
+            return Ok((false, 0));
+        }
+
+        for (position, symbol) in input.iter().enumerate() {
+            if current.iter().all(|&state| dead[state]) {
+                return Ok((false, position));
+            }
+
+            current = self.step(&current, symbol);
+            check_size(&current)?;
+        }
+
+        Ok((self.any_accept(&current), input.len()))
+    }
+
+    /// Whether each state is one of the `dead_states`.
+    fn dead_mask(&self) -> Vec<bool> {
+        let mut dead = vec![false; self.len()];
+        for index in self.dead_states() {
+            dead[index] = true;
+        }
 
-        let mut non_marked_state = vec![0];
+        dead
+    }
+
+    /// Finds one accepting run for `input` as the sequence of visited states,
+    /// epsilon moves included, preferring runs with fewer moves.
+    pub fn accepting_run(&self, input: &[&str]) -> Option<Vec<usize>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let start = (self.entry_state, 0);
+        let mut parents: HashMap<_, Option<(usize, usize)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        parents.insert(start, None);
+        queue.push_back(start);
 
-        while let Some(non_marked) = non_marked_state.pop() {
-            for symbol in &self.alphabet {
-                let state = &afd_automata[non_marked];
-                let mut next_states = Vec::new();
-                for tag in state.tags() {
-                    let mut moved =
-                        self.move_from_with(self.find(&tag.clone().into()).unwrap(), symbol);
-                    next_states.append(&mut moved);
+        while let Some((state, position)) = queue.pop_front() {
+            if position == input.len() && self.accept_states.contains(&state) {
+                let mut run = vec![state];
+                let mut current = (state, position);
+                while let Some(&Some(parent)) = parents.get(&current) {
+                    run.push(parent.0);
+                    current = parent;
                 }
 
-                if next_states.is_empty() {
-                    continue;
+                run.reverse();
+                return Some(run);
+            }
+
+            let mut next = Vec::new();
+            for transition in self.transitions_from(state) {
+                if transition.is_epsilon() {
+                    next.push((transition.end_state(), position));
                 }
+            }
 
-                let e_closure_set = self.e_closure_set(&next_states);
+            if let Some(symbol) = input.get(position) {
+                for end_state in self.move_from_with(state, symbol) {
+                    next.push((end_state, position + 1));
+                }
+            }
 
-                let mut accept_state = false;
-                let state = {
-                    let mut set = Set::new();
-                    for state in e_closure_set {
-                        set.append(&mut self[state].tags().clone());
-                        accept_state = accept_state || self.accept_states.contains(&state);
-                    }
+            for configuration in next {
+                if let Entry::Vacant(entry) = parents.entry(configuration) {
+                    entry.insert(Some((state, position)));
+                    queue.push_back(configuration);
+                }
+            }
+        }
 
-                    State::from(set)
-                };
+        None
+    }
 
-                if !afd_automata.states.contains(&state) {
-                    afd_automata.push_state(state.clone());
-                    let index = afd_automata.find(&state).unwrap();
-                    non_marked_state.push(index);
+    /// Counts how many prefixes of `input` (the empty one included) are accepted.
+    pub fn accept_count_along(&self, input: &[&str]) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
 
-                    if accept_state {
-                        afd_automata.push_accept_state(index);
-                    }
-                }
+        let mut current = self.e_closure_set(&[self.entry_state]);
+        let mut count = if self.any_accept(&current) { 1 } else { 0 };
+        for symbol in input {
+            current = self.step(&current, symbol);
+            if current.is_empty() {
+                break;
+            }
 
-                let index = afd_automata.find(&state).unwrap();
-                afd_automata.push_transition_from(non_marked, Transition::new(symbol, index));
+            if self.any_accept(&current) {
+                count += 1;
             }
         }
 
-        if afd_automata.is_deterministic() {
-            return afd_automata;
+        count
+    }
+
+    /// Number of distinct states entered while reading `input`, the entry state
+    /// included. The simulation stops as soon as no state is left.
+    pub fn count_states_visited(&self, input: &[&str]) -> usize {
+        if self.is_empty() {
+            return 0;
         }
 
-        afd_automata.push_state("!".into());
-        let never_state_index = afd_automata.len() - 1;
-        for state_transition in &mut afd_automata.transitions {
-            if state_transition.len() == afd_automata.alphabet.len() {
-                continue;
+        let mut current = self.e_closure_set(&[self.entry_state]);
+        let mut visited: HashSet<_> = current.iter().cloned().collect();
+        for symbol in input {
+            current = self.step(&current, symbol);
+            if current.is_empty() {
+                break;
             }
 
-            let mut existing = HashSet::new();
-            for transition in state_transition.iter() {
-                existing.insert(transition.symbol().to_owned());
+            visited.extend(current.iter().cloned());
+        }
+
+        visited.len()
+    }
+
+    /// Product of the weights along the deterministic run for `input` (the
+    /// first transition is taken for every symbol, unweighted ones count as
+    /// 1.0), `None` unless the run exists and ends in an accept state.
+    pub fn path_weight(&self, input: &[&str]) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut current = self.entry_state;
+        let mut weight = 1.0;
+        for symbol in input {
+            match self.transitions[current]
+                .iter()
+                .find(|t| t.symbol() == *symbol)
+            {
+                Some(transition) => {
+                    weight *= transition.weight().unwrap_or(1.0);
+                    current = transition.end_state();
+                }
+                None => current = self.default_transitions[current]?,
             }
+        }
+
+        if self.accept_states.contains(&current) {
+            Some(weight)
+        } else {
+            None
+        }
+    }
 
-            for symbol in &afd_automata.alphabet {
-                if !existing.contains(symbol) {
-                    state_transition.push(Transition::new(symbol, never_state_index));
+    fn step(&self, current: &[usize], symbol: &str) -> Vec<usize> {
+        let mut next_states = Vec::new();
+        for &state in current {
+            for end_state in self.move_from_with(state, symbol) {
+                if !next_states.contains(&end_state) {
+                    next_states.push(end_state);
                 }
             }
         }
 
-        afd_automata
+        self.e_closure_set(&next_states)
     }
-}
 
-impl fmt::Display for Automata {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Estados")?;
-        for (index, state) in self.states.iter().enumerate() {
-            write!(f, "{} = {{", index)?;
-            for tag in state.tags() {
-                write!(f, " {}", tag)?;
+    fn any_accept(&self, states: &[usize]) -> bool {
+        states
+            .iter()
+            .any(|state| self.accept_states.contains(state))
+    }
+
+    /// Like `accepts`, but tells apart input that can't be read over the
+    /// alphabet (epsilon or unknown symbols) from input that is just rejected.
+    /// Unknown symbols are only allowed when there are default transitions.
+    pub fn accepts_detailed(&self, input: &[&str]) -> Acceptance {
+        let open_alphabet = self.default_transitions.iter().any(Option::is_some);
+        for (position, symbol) in input.iter().enumerate() {
+            if symbol.is_empty() {
+                let reason = "epsilon can't be part of the input".to_owned();
+                return Acceptance::InvalidInput { position, reason };
+            }
+
+            if !open_alphabet && !self.alphabet.iter().any(|s| s == symbol) {
+                let reason = format!("symbol {:?} is not in the alphabet", symbol);
+                return Acceptance::InvalidInput { position, reason };
             }
-            writeln!(f, " }}")?;
         }
 
-        writeln!(f, "\nEstados de aceptación")?;
-        for index in &self.accept_states {
-            write!(f, "{} ", index)?;
+        if self.accepts(input) {
+            Acceptance::Accepted
+        } else {
+            Acceptance::Rejected
         }
+    }
 
-        writeln!(f, "\n\nAlfabeto")?;
-        for symbol in &self.alphabet {
-            write!(f, "{} ", symbol)?;
+    /// Splits `input` into alphabet symbols, always taking the longest symbol
+    /// that matches, returns `None` if some part of it can't be matched (so
+    /// with an empty alphabet only the empty input can be split).
+    pub fn tokenize<'a>(&self, input: &'a str) -> Option<Vec<&'a str>> {
+        let mut tokens = Vec::new();
+        let mut rest = input;
+        while !rest.is_empty() {
+            let symbol = self
+                .alphabet
+                .iter()
+                .filter(|s| !s.is_empty() && rest.starts_with(s.as_str()))
+                .max_by_key(|s| s.len())?;
+
+            tokens.push(&rest[..symbol.len()]);
+            rest = &rest[symbol.len()..];
         }
 
-        writeln!(f, "\n\nTransiciones")?;
-        for (index, state_transition) in self.transitions.iter().enumerate() {
-            for transition in state_transition {
-                let symbol = if transition.symbol() == "" {
-                    "-1"
-                } else {
-                    transition.symbol()
-                };
+        Some(tokens)
+    }
 
-                writeln!(f, "{} {} {}", index, symbol, transition.end_state())?;
-            }
+    /// Checks the automaton against words that should be accepted and words
+    /// that should be rejected, words that can't be tokenized are rejected.
+    pub fn classify_report(&self, accept: &[&str], reject: &[&str]) -> ClassifyReport {
+        let dead = self.dead_mask();
+        let accepts = |word: &str| match self.tokenize(word) {
+            Some(tokens) => self.accepts_consumed(&tokens, usize::MAX, &dead).unwrap().0,
+            None => false,
+        };
+
+        ClassifyReport {
+            false_accepts: reject
+                .iter()
+                .filter(|word| accepts(word))
+                .map(|word| (*word).to_owned())
+                .collect(),
+            false_rejects: accept
+                .iter()
+                .filter(|word| !accepts(word))
+                .map(|word| (*word).to_owned())
+                .collect(),
         }
+    }
 
-        Ok(())
+    pub fn accepts_with_eof(&self, input: &[&str], eof: &str) -> bool {
+        let mut input = input.to_vec();
+        input.push(eof);
+        self.accepts(&input)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn epsilon_closure_single(&self, index: impl AutomataIndex) -> Vec<usize> {
+        self.e_closure_set(&[index.index(self)])
+    }
 
-    #[test]
-    fn e_closure_set() {
-        let mut automata = Automata::new();
-        automata.push_state("0".into());
-        automata.push_state("1".into());
-        automata.push_state("2".into());
-        automata.push_state("3".into());
-        automata.push_state("4".into());
-        automata.push_state("5".into());
+    /// States reachable from `index` through its epsilon closure, one move on
+    /// `symbol` and the epsilon closure of that, sorted.
+    pub fn symbol_transition_closure(&self, index: impl AutomataIndex, symbol: &str) -> Vec<usize> {
+        let closure = self.e_closure_set(&[index.index(self)]);
+        let mut states = self.step(&closure, symbol);
+        states.sort_unstable();
+        states
+    }
 
-        automata.push_accept_state(5);
+    /// Panics if a symbol of the alphabet stands for epsilon, see
+    /// `try_to_deterministic`.
+    pub fn to_deterministic(&self) -> Self {
+        self.try_to_deterministic()
+            .unwrap_or_else(|error| panic!("Invalid alphabet: {}", error))
+    }
 
-        automata.push_transition_from(0, Transition::new("a", 1));
-        automata.push_transition_from(0, Transition::new("", 2));
-        automata.push_transition_from(0, Transition::new("", 3));
-        automata.push_transition_from(1, Transition::new("", 3));
-        automata.push_transition_from(2, Transition::new("b", 3));
-        automata.push_transition_from(3, Transition::new("", 4));
-        automata.push_transition_from(4, Transition::new("a", 5));
+    /// Like `to_deterministic`, but an alphabet symbol that stands for epsilon
+    /// (`""` or `"-1"`) is reported as an error.
+    pub fn try_to_deterministic(&self) -> Result<Self, AutomataError> {
+        for symbol in self.alphabet.iter() {
+            check_symbol(symbol)?;
+        }
 
-        let set_01 = automata.e_closure_set(&[0, 1]);
-        let set_345 = automata.e_closure_set(&[3, 4, 5]);
+        Ok(self.to_deterministic_with(TrapPolicy::Unnamed))
+    }
 
-        assert_eq!(&set_01, &[0, 1, 3, 4, 2]);
-        assert_eq!(&set_345, &[3, 4, 5]);
+    pub fn to_deterministic_with(&self, policy: TrapPolicy) -> Self {
+        let (afd_automata, _) = self.determinize(&self.alphabet, usize::MAX, &policy);
+        afd_automata
     }
 
-    #[test]
-    fn find_state() {
-        let mut automata = Automata::new();
-        automata.push_state("p".into());
-        automata.push_state("q".into());
-        automata.push_state("r".into());
+    /// Like `to_deterministic`, but an automaton that accepts no word at all
+    /// (most likely an authoring mistake, like an entry state with no way out)
+    /// is reported as an error.
+    pub fn to_deterministic_strict(&self) -> Result<Self, AutomataError> {
+        let afd_automata = self.try_to_deterministic()?;
+        if afd_automata.is_empty_language() {
+            return Err(AutomataError::EmptyLanguage);
+        }
 
-        assert_eq!(Some(0), automata.find(&"p".into()));
-        assert_eq!(Some(1), automata.find(&"q".into()));
-        assert_eq!(Some(2), automata.find(&"r".into()));
+        Ok(afd_automata)
+    }
+
+    /// Like `to_deterministic`, but when every state of the DFA accepts (so
+    /// there's no reachable trap state) the result is collapsed into the single
+    /// state universal automaton, holding all the tags.
+    pub fn to_deterministic_collapsing_universal(&self) -> Self {
+        let afd_automata = self.to_deterministic();
+        if afd_automata.is_empty() || afd_automata.accept_states.len() < afd_automata.len() {
+            return afd_automata;
+        }
+
+        afd_automata.rebuild(&vec![Some(0); afd_automata.len()])
+    }
+
+    /// Like `to_deterministic`, but every state is tagged just with its index
+    /// while the merged NFA tags are kept apart, see `provenance`.
+    pub fn to_deterministic_with_provenance(&self) -> Self {
+        let mut afd_automata = self.to_deterministic();
+        afd_automata.provenance = Vec::with_capacity(afd_automata.len());
+        for (index, state) in afd_automata.states.iter_mut().enumerate() {
+            let tags = std::mem::replace(state, index.to_string().into());
+            afd_automata.provenance.push(tags.tags().clone());
+        }
+
+        afd_automata.reindex_states();
+        afd_automata
+    }
+
+    /// Tags of the NFA states a state built by `to_deterministic_with_provenance`
+    /// stands for, `None` for any other state. Only meaningful while the DFA
+    /// is left as it was built.
+    pub fn provenance(&self, index: impl AutomataIndex) -> Option<&Set<String>> {
+        self.provenance.get(index.index(self))
+    }
+
+    /// Like `to_deterministic`, but keeps for every DFA state the lowest index
+    /// among the NFA accept states it stands for, see `accept_priority`. With
+    /// one accept state per rule, ordered by priority, that's the rule a
+    /// scanner should report when it stops there.
+    pub fn to_deterministic_with_priorities(&self) -> Self {
+        let (mut afd_automata, subsets) = self.to_deterministic_with_subsets();
+        afd_automata.accept_priorities = subsets
+            .iter()
+            .map(|subset| {
+                let accepting = subset.iter().filter(|i| self.accept_states.contains(i));
+                accepting.min().copied()
+            })
+            .collect();
+
+        afd_automata
+    }
+
+    /// Lowest NFA accept state merged into a state built by
+    /// `to_deterministic_with_priorities`, `None` if there's none (or the
+    /// automaton wasn't built that way). Only meaningful while the DFA is left
+    /// as it was built.
+    pub fn accept_priority(&self, index: impl AutomataIndex) -> Option<usize> {
+        self.accept_priorities
+            .get(index.index(self))
+            .copied()
+            .flatten()
+    }
+
+    /// For a DFA built by `to_deterministic` (or one of its variants), the state
+    /// standing for exactly these NFA states (an epsilon closure, in any
+    /// order), if the construction reached it. The trap state stands for the
+    /// empty subset. Only meaningful while the DFA is left as it was built.
+    pub fn dfa_state_for(&self, nfa_states: &[usize]) -> Option<usize> {
+        let mut subset = nfa_states.to_vec();
+        subset.sort_unstable();
+        subset.dedup();
+        self.subset_indices.get(&subset).copied()
+    }
+
+    /// Also returns, for every DFA state, the sorted indices of the NFA states
+    /// it stands for (empty for the trap state), regardless of their tags.
+    /// These are the subsets recorded by the construction, see `dfa_state_for`.
+    pub fn to_deterministic_with_subsets(&self) -> (Self, Vec<Vec<usize>>) {
+        let afd_automata = self.to_deterministic();
+        let mut subsets = vec![Vec::new(); afd_automata.len()];
+        for (subset, &index) in &afd_automata.subset_indices {
+            subsets[index] = subset.clone();
+        }
+
+        (afd_automata, subsets)
+    }
+
+    /// Runs the subset construction creating at most `max_states` states (the
+    /// entry state is always created), returns the DFA and whether it's complete.
+    /// A partial result is sound: every accepted word belongs to the language,
+    /// transitions that would need a new state are just left out.
+    pub fn to_deterministic_budgeted(&self, max_states: usize) -> (Self, bool) {
+        self.determinize(&self.alphabet, max_states, &TrapPolicy::Unnamed)
+    }
+
+    /// Determinizes through the subset construction, reporting how much work
+    /// it took. Unlike `to_deterministic` this never takes the partial DFA
+    /// shortcut, so the numbers always describe the full construction.
+    pub fn to_deterministic_stats(&self) -> (Self, DeterminizeStats) {
+        check_alphabet(&self.alphabet);
+        let mut stats = DeterminizeStats::default();
+        if self.is_empty() {
+            return (self.to_deterministic(), stats);
+        }
+
+        let (mut afd_automata, _) =
+            self.subset_construction(&self.alphabet, usize::MAX, &mut stats);
+        afd_automata.complete_with_trap();
+        (afd_automata, stats)
+    }
+
+    /// Determinizes over `alphabet`, which must contain every symbol of the
+    /// automaton's own alphabet (the first one missing is the error); the extra
+    /// symbols lead to the trap state.
+    pub fn to_deterministic_over(&self, alphabet: &[&str]) -> Result<Self, AutomataError> {
+        if let Some(symbol) = self
+            .alphabet
+            .iter()
+            .find(|s| !alphabet.contains(&s.as_str()))
+        {
+            return Err(AutomataError::MissingSymbol(symbol.clone()));
+        }
+
+        for symbol in alphabet {
+            check_symbol(symbol)?;
+        }
+
+        let alphabet = Arc::new(alphabet.iter().map(|s| (*s).to_owned()).collect());
+        let (afd_automata, _) = self.determinize(&alphabet, usize::MAX, &TrapPolicy::Unnamed);
+        Ok(afd_automata)
+    }
+
+    fn determinize(
+        &self,
+        alphabet: &Arc<Vec<String>>,
+        max_states: usize,
+        policy: &TrapPolicy,
+    ) -> (Self, bool) {
+        check_alphabet(alphabet);
+        if self.is_empty() {
+            let mut afd_automata = Automata::new();
+            afd_automata.alphabet = Arc::clone(alphabet);
+            return (afd_automata, true);
+        }
+
+        if self.is_empty_language() {
+            return (self.empty_language_dfa(alphabet, policy), true);
+        }
+
+        let mut afd_automata = match self.relabel_partial_dfa(alphabet) {
+            Some(afd_automata) if afd_automata.len() <= max_states => afd_automata,
+            _ => {
+                let mut stats = DeterminizeStats::default();
+                let (afd_automata, complete) =
+                    self.subset_construction(alphabet, max_states, &mut stats);
+                if !complete {
+                    return (afd_automata, false);
+                }
+
+                afd_automata
+            }
+        };
+
+        afd_automata.complete_with(policy);
+        (afd_automata, true)
+    }
+
+    /// The canonical DFA for a language without words: a single non-accepting
+    /// state, tagged like the entry subset, looping on every symbol (unless
+    /// the policy leaves the DFA partial, then it has no transitions at all).
+    fn empty_language_dfa(&self, alphabet: &Arc<Vec<String>>, policy: &TrapPolicy) -> Self {
+        let mut subset = self.e_closure_set(&[self.entry_state]);
+        subset.sort_unstable();
+
+        let mut afd_automata = Automata::new();
+        afd_automata.alphabet = Arc::clone(alphabet);
+        afd_automata.push_subset_state(self, subset);
+        if *policy != TrapPolicy::None {
+            for symbol in alphabet.iter() {
+                afd_automata.push_transition_from(0, Transition::new(symbol, 0));
+            }
+        }
+
+        afd_automata
+    }
+
+    /// If the automaton is already a (possibly partial) DFA its reachable part
+    /// is copied in the same order the subset construction would produce,
+    /// without computing any closure.
+    fn relabel_partial_dfa(&self, alphabet: &Arc<Vec<String>>) -> Option<Self> {
+        if self.is_empty()
+            || !self.is_deterministic()
+            || self.default_transitions.iter().any(Option::is_some)
+        {
+            return None;
+        }
+
+        let mut mapping = vec![None; self.len()];
+        let mut afd_automata = Automata::new();
+        afd_automata.alphabet = Arc::clone(alphabet);
+
+        let mut non_marked_state = VecDeque::new();
+        mapping[self.entry_state] = Some(0);
+        afd_automata.push_state(self[self.entry_state].clone());
+        afd_automata
+            .subset_indices
+            .insert(vec![self.entry_state], 0);
+        non_marked_state.push_back(self.entry_state);
+
+        while let Some(non_marked) = non_marked_state.pop_front() {
+            let index = mapping[non_marked].unwrap();
+            if self.accept_states.contains(&non_marked) {
+                afd_automata.push_accept_state(index);
+            }
+
+            for symbol in alphabet.iter() {
+                let end_state = match self.move_from_with(non_marked, symbol).first() {
+                    Some(&end_state) => end_state,
+                    None => continue,
+                };
+
+                let end_index = match mapping[end_state] {
+                    Some(end_index) => end_index,
+                    None => {
+                        let end_index = afd_automata.len();
+                        mapping[end_state] = Some(end_index);
+                        afd_automata.push_state(self[end_state].clone());
+                        let subset = vec![end_state];
+                        afd_automata.subset_indices.insert(subset, end_index);
+                        non_marked_state.push_back(end_state);
+                        end_index
+                    }
+                };
+
+                afd_automata.push_transition_from(index, Transition::new(symbol, end_index));
+            }
+        }
+
+        Some(afd_automata)
+    }
+
+    fn subset_construction(
+        &self,
+        alphabet: &Arc<Vec<String>>,
+        max_states: usize,
+        stats: &mut DeterminizeStats,
+    ) -> (Self, bool) {
+        let mut afd_automata = Automata::new();
+        afd_automata.alphabet = Arc::clone(alphabet);
+
+        // DFA states are told apart by their sorted NFA subsets, the tags are
+        // only merged to label them
+        let mut entry_subset = self.e_closure_set(&[self.entry_state]);
+        entry_subset.sort_unstable();
+        afd_automata.push_subset_state(self, entry_subset.clone());
+
+        let mut non_marked_state = VecDeque::new();
+        non_marked_state.push_back((0, entry_subset));
+        let mut complete = true;
+        stats.states_created = 1;
+        stats.max_frontier = 1;
+
+        while let Some((non_marked, subset)) = non_marked_state.pop_front() {
+            for symbol in alphabet.iter() {
+                let mut next_subset = self.step(&subset, symbol);
+                if next_subset.is_empty() {
+                    continue;
+                }
+
+                next_subset.sort_unstable();
+                stats.membership_checks += 1;
+                let index = match afd_automata.subset_indices.get(&next_subset) {
+                    Some(&index) => index,
+                    None => {
+                        if afd_automata.len() >= max_states {
+                            complete = false;
+                            continue;
+                        }
+
+                        let index = afd_automata.push_subset_state(self, next_subset.clone());
+                        non_marked_state.push_back((index, next_subset));
+                        stats.states_created += 1;
+                        stats.max_frontier = stats.max_frontier.max(non_marked_state.len());
+                        index
+                    }
+                };
+
+                afd_automata.push_transition_from(non_marked, Transition::new(symbol, index));
+            }
+        }
+
+        (afd_automata, complete)
+    }
+
+    /// Pushes the DFA state standing for the NFA states in `subset`, tagged with
+    /// all their tags. Being accepting depends only on the subset, so finding
+    /// the state again by another path can't change it.
+    fn push_subset_state(&mut self, nfa: &Automata, subset: Vec<usize>) -> usize {
+        let mut set = Set::new();
+        for &state in &subset {
+            set.append(&mut nfa[state].tags().clone());
+        }
+
+        let index = self.len();
+        self.push_state(State::from(set));
+        if nfa.any_accept(&subset) {
+            self.push_accept_state(index);
+        }
+
+        self.subset_indices.insert(subset, index);
+        index
+    }
+
+    fn complete_with_trap(&mut self) {
+        self.complete_with(&TrapPolicy::Unnamed);
+    }
+
+    fn complete_with(&mut self, policy: &TrapPolicy) {
+        let trap = match policy {
+            TrapPolicy::None => None,
+            TrapPolicy::Unnamed => Some("!"),
+            TrapPolicy::Named(name) => Some(name.as_str()),
+        };
+
+        if let (Some(trap), false) = (trap, self.is_complete()) {
+            self.push_state(trap.into());
+            let never_state_index = self.len() - 1;
+            self.subset_indices.insert(Vec::new(), never_state_index);
+            for symbol in self.alphabet.iter() {
+                let transition = Transition::new(symbol, never_state_index);
+                self.transitions[never_state_index].push(transition);
+            }
+
+            for state_transition in &mut self.transitions {
+                if state_transition.len() == self.alphabet.len() {
+                    continue;
+                }
+
+                let mut existing = HashSet::new();
+                for transition in state_transition.iter() {
+                    existing.insert(transition.symbol().to_owned());
+                }
+
+                for symbol in self.alphabet.iter() {
+                    if !existing.contains(symbol) {
+                        state_transition.push(Transition::new(symbol, never_state_index));
+                    }
+                }
+            }
+        }
+
+        let alphabet = &self.alphabet;
+        for state_transition in &mut self.transitions {
+            state_transition.sort_by_key(|t| {
+                let position = alphabet.iter().position(|s| s == t.symbol());
+                (position, t.end_state())
+            });
+        }
+    }
+
+    /// DFA for the intersection of both languages over their shared symbols:
+    /// the product of both determinized automata, tagged `(left,right)`.
+    pub fn intersection(&self, other: &Automata) -> Self {
+        let left = self.to_deterministic();
+        let right = other.to_deterministic();
+        let (product, _) = left.product_with_labels(&right);
+        product
+    }
+
+    /// DFA for the words over the alphabet that aren't accepted. Flipping the
+    /// accept states is only right for a complete DFA, so this goes through
+    /// `to_deterministic` (and its trap state) first. Without states nothing
+    /// is accepted, so the complement is a single accept state looping on
+    /// every symbol.
+    pub fn complement(&self) -> Self {
+        if self.is_empty() {
+            let mut automata = Automata::new();
+            automata.alphabet = Arc::clone(&self.alphabet);
+            automata.push_state("0".into());
+            automata.push_accept_state(0);
+            for symbol in self.alphabet.iter() {
+                automata.push_transition_from(0, Transition::new(symbol, 0));
+            }
+
+            return automata;
+        }
+
+        let mut automata = self.to_deterministic();
+        let accept_states = std::mem::take(&mut automata.accept_states);
+        for index in 0..automata.len() {
+            if !accept_states.contains(&index) {
+                automata.push_accept_state(index);
+            }
+        }
+
+        automata
+    }
+
+    /// Builds the product automaton accepting L(self) ∩ L(other), together with
+    /// the `(i, j)` pair of component states every product state comes from.
+    pub fn product_with_labels(&self, other: &Automata) -> (Self, Vec<(usize, usize)>) {
+        let mut product = Automata::new();
+        for symbol in self.alphabet.iter() {
+            if other.alphabet.contains(symbol) {
+                product.push_symbol(symbol);
+            }
+        }
+
+        if self.is_empty() || other.is_empty() {
+            return (product, Vec::new());
+        }
+
+        let entry = (self.entry_state, other.entry_state);
+        let mut pairs = vec![entry];
+        let mut indices = HashMap::new();
+        indices.insert(entry, 0);
+        product.push_state(self.pair_state(other, entry));
+
+        let mut current = 0;
+        while current < pairs.len() {
+            let (i, j) = pairs[current];
+            if self.accept_states.contains(&i) && other.accept_states.contains(&j) {
+                product.push_accept_state(current);
+            }
+
+            let mut next_pairs = Vec::new();
+            for transition in self.transitions_from(i) {
+                if transition.symbol() == "" {
+                    next_pairs.push(("", (transition.end_state(), j)));
+                }
+            }
+
+            for transition in other.transitions_from(j) {
+                if transition.symbol() == "" {
+                    next_pairs.push(("", (i, transition.end_state())));
+                }
+            }
+
+            for symbol in self.alphabet.iter().filter(|s| other.alphabet.contains(s)) {
+                for left in self.move_from_with(i, symbol) {
+                    for right in other.move_from_with(j, symbol) {
+                        next_pairs.push((symbol, (left, right)));
+                    }
+                }
+            }
+
+            let mut transitions = Vec::new();
+            for (symbol, pair) in next_pairs {
+                let index = match indices.get(&pair) {
+                    Some(&index) => index,
+                    None => {
+                        let index = pairs.len();
+                        pairs.push(pair);
+                        indices.insert(pair, index);
+                        product.push_state(self.pair_state(other, pair));
+                        index
+                    }
+                };
+
+                transitions.push(Transition::new(symbol, index));
+            }
+
+            for transition in transitions {
+                product.push_transition_from(current, transition);
+            }
+
+            current += 1;
+        }
+
+        (product, pairs)
+    }
+
+    fn pair_state(&self, other: &Automata, (i, j): (usize, usize)) -> State {
+        format!("({},{})", self[i].label(), other[j].label()).into()
+    }
+
+    /// Merges accept states whose outgoing transitions are identical (treating
+    /// self-loops as equal), which is a cheap, sound reduction of the automaton.
+    /// It doesn't guarantee a minimal result, use it as a fast preprocessing step.
+    pub fn collapse_equivalent_accept_states(&self) -> Self {
+        let mut mapping = Vec::with_capacity(self.len());
+        let mut representatives = HashMap::new();
+        let mut next_index = 0;
+
+        for index in 0..self.len() {
+            if self.accept_states.contains(&index) {
+                let target = |end_state| {
+                    if end_state == index {
+                        None
+                    } else {
+                        Some(end_state)
+                    }
+                };
+                let mut signature: Vec<_> = self.transitions[index]
+                    .iter()
+                    .map(|t| (t.symbol(), target(t.end_state())))
+                    .collect();
+                signature.sort();
+                let default_target = self.default_transitions[index].map(target);
+
+                let signature = (signature, default_target);
+                if let Some(&representative) = representatives.get(&signature) {
+                    mapping.push(Some(representative));
+                    continue;
+                }
+
+                representatives.insert(signature, next_index);
+            }
+
+            mapping.push(Some(next_index));
+            next_index += 1;
+        }
+
+        self.rebuild(&mapping)
+    }
+
+    /// Maps every state to a new index (or drops it), merging the states that
+    /// share one. Merged states must agree on where their default transitions
+    /// lead, there's no way to keep both.
+    fn rebuild(&self, mapping: &[Option<usize>]) -> Self {
+        let len = mapping.iter().flatten().max().map_or(0, |max| max + 1);
+        let mut sets = vec![Set::new(); len];
+        for (old, new) in mapping.iter().enumerate() {
+            if let Some(new) = new {
+                sets[*new].append(&mut self[old].tags().clone());
+            }
+        }
+
+        let mut automata = Automata::new();
+        automata.alphabet = self.alphabet.clone();
+        for set in sets {
+            automata.push_state(State::from(set));
+        }
+
+        for (old, new) in mapping.iter().enumerate() {
+            let new = match new {
+                Some(new) => *new,
+                None => continue,
+            };
+
+            if self.accept_states.contains(&old) && !automata.accept_states.contains(&new) {
+                automata.push_accept_state(new);
+            }
+
+            for transition in &self.transitions[old] {
+                if let Some(end_state) = mapping[transition.end_state()] {
+                    automata
+                        .push_transition_from(new, Transition::new(transition.symbol(), end_state));
+                }
+            }
+        }
+
+        let mut seen = vec![false; len];
+        for (old, new) in mapping.iter().enumerate() {
+            if let Some(new) = *new {
+                let end_state = self.default_transitions[old].and_then(|end| mapping[end]);
+                if seen[new] {
+                    assert_eq!(
+                        automata.default_transitions[new], end_state,
+                        "Merged states disagree on their default transition"
+                    );
+                }
+
+                seen[new] = true;
+                automata.default_transitions[new] = end_state;
+            }
+        }
+
+        if let Some(entry_state) = mapping[self.entry_state] {
+            automata.entry_state = entry_state;
+        }
+
+        automata
+    }
+
+    /// Decides strong bisimilarity of both automata seen as labeled transition
+    /// systems, where being an accept state is part of a state's observation.
+    /// A default transition counts as one more label.
+    pub fn bisimulation_equivalent(&self, other: &Automata) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return self.is_empty() && other.is_empty();
+        }
+
+        let offset = self.len();
+        let mut successors = Vec::with_capacity(offset + other.len());
+        let mut defaults = Vec::with_capacity(offset + other.len());
+        let mut blocks = Vec::with_capacity(offset + other.len());
+        for (automata, offset) in &[(self, 0), (other, offset)] {
+            for index in 0..automata.len() {
+                let state_successors: Vec<_> = automata.transitions[index]
+                    .iter()
+                    .map(|t| (t.symbol().to_owned(), t.end_state() + offset))
+                    .collect();
+
+                successors.push(state_successors);
+                defaults.push(automata.default_transitions[index].map(|end| end + offset));
+                blocks.push(automata.accept_states.contains(&index) as usize);
+            }
+        }
+
+        let mut count = 0;
+        loop {
+            let mut signatures = HashMap::new();
+            let mut next_blocks = Vec::with_capacity(blocks.len());
+            for (index, state_successors) in successors.iter().enumerate() {
+                let signature: BTreeSet<_> = state_successors
+                    .iter()
+                    .map(|(symbol, end_state)| (symbol.as_str(), blocks[*end_state]))
+                    .collect();
+
+                let default_block = defaults[index].map(|end_state| blocks[end_state]);
+                let next_index = signatures.len();
+                let block = *signatures
+                    .entry((blocks[index], default_block, signature))
+                    .or_insert(next_index);
+                next_blocks.push(block);
+            }
+
+            blocks = next_blocks;
+            if signatures.len() == count {
+                break;
+            }
+
+            count = signatures.len();
+        }
+
+        blocks[self.entry_state] == blocks[other.entry_state + offset]
+    }
+
+    /// Appends the states, transitions and accept states of `other`, shifting
+    /// its indices past the current states, and returns that offset. Missing
+    /// symbols are added to the alphabet, the entry state is left untouched.
+    pub fn merge(&mut self, other: &Automata) -> usize {
+        self.forget_determinization();
+        let offset = self.len();
+        for symbol in other.alphabet.iter() {
+            if !self.alphabet.contains(symbol) {
+                self.push_symbol(symbol);
+            }
+        }
+
+        for state in &other.states {
+            self.push_state(state.clone());
+        }
+
+        for (index, state_transition) in other.transitions.iter().enumerate() {
+            for transition in state_transition {
+                let transition =
+                    Transition::new(transition.symbol(), transition.end_state() + offset);
+                self.push_transition_from(index + offset, transition);
+            }
+
+            if let Some(end_state) = other.default_transitions[index] {
+                self.default_transitions[index + offset] = Some(end_state + offset);
+            }
+        }
+
+        for &index in &other.accept_states {
+            self.push_accept_state(index + offset);
+        }
+
+        offset
+    }
+
+    /// Longest string every accepted word ends with, computed as the common
+    /// prefix of the reversed language (empty when the language is empty).
+    pub fn common_suffix(&self) -> String {
+        let mut suffix = self.reverse().common_prefix();
+        suffix.reverse();
+        suffix.concat()
+    }
+
+    fn common_prefix(&self) -> Vec<String> {
+        let mut prefix = Vec::new();
+        if self.is_empty() {
+            return prefix;
+        }
+
+        let mut dead = vec![false; self.len()];
+        for index in self.dead_states() {
+            dead[index] = true;
+        }
+
+        let mut current = self.e_closure_set(&[self.entry_state]);
+        while !self.any_accept(&current) {
+            let mut live = Vec::new();
+            for symbol in self.alphabet.iter() {
+                let next_states = self.step(&current, symbol);
+                if next_states.iter().any(|&state| !dead[state]) {
+                    live.push((symbol, next_states));
+                }
+            }
+
+            if live.len() != 1 {
+                break;
+            }
+
+            let (symbol, next_states) = live.pop().unwrap();
+            prefix.push(symbol.clone());
+            current = next_states;
+        }
+
+        prefix
+    }
+
+    /// NFA for the reversed language: every transition points the other way,
+    /// the old entry is the only accept state and a new entry tagged "^" has
+    /// epsilon transitions to the old accept states. Default transitions are
+    /// only followed for the symbols of the alphabet.
+    pub fn reverse(&self) -> Self {
+        let mut automata = Automata::new();
+        automata.alphabet = self.alphabet.clone();
+        for state in &self.states {
+            automata.push_state(state.clone());
+        }
+
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            for transition in state_transition {
+                let reversed = Transition::new(transition.symbol(), index);
+                automata.push_transition_from(transition.end_state(), reversed);
+            }
+
+            if let Some(end_state) = self.default_transitions[index] {
+                for symbol in self.alphabet.iter().filter(|s| !s.is_empty()) {
+                    if state_transition.iter().all(|t| t.symbol() != symbol) {
+                        automata.push_transition_from(end_state, Transition::new(symbol, index));
+                    }
+                }
+            }
+        }
+
+        automata.push_state("^".into());
+        let entry_state = automata.len() - 1;
+        for &index in &self.accept_states {
+            automata.push_transition_from(entry_state, Transition::epsilon(index));
+        }
+
+        automata.set_entry_state(entry_state);
+        if !self.is_empty() {
+            automata.push_accept_state(self.entry_state);
+        }
+
+        automata
+    }
+
+    /// Accepts every suffix of every accepted word: a new entry tagged "^" has
+    /// an epsilon transition to each reachable state.
+    pub fn suffix_language(&self) -> Self {
+        let mut automata = self.clone();
+        automata.push_state("^".into());
+        let entry_state = automata.len() - 1;
+        for index in self.reachable_states() {
+            automata.push_transition_from(entry_state, Transition::epsilon(index));
+        }
+
+        automata.set_entry_state(entry_state);
+        automata
+    }
+
+    /// Accepts every factor (contiguous piece) of every accepted word. Built on
+    /// `suffix_language`, accepting also in every reachable state from which an
+    /// accept state can still be reached.
+    pub fn factor_language(&self) -> Self {
+        let mut automata = self.suffix_language();
+        let dead = self.dead_states();
+        for index in self.reachable_states() {
+            if !dead.contains(&index) && !automata.accept_states.contains(&index) {
+                automata.push_accept_state(index);
+            }
+        }
+
+        automata
+    }
+
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(DotOptions::default())
+    }
+
+    /// Graphviz DOT source for the automaton, readable back with `from_dot`.
+    pub fn to_dot_with(&self, options: DotOptions) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph {\n    rankdir=LR;\n");
+        dot.push_str("    start [shape=point, style=invis];\n");
+        for (index, state) in self.states.iter().enumerate() {
+            let shape = if self.accept_states.contains(&index) {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+
+            let label = if options.subset_labels {
+                state.label()
+            } else {
+                index.to_string()
+            };
+
+            dot.push_str(&format!(
+                "    {} [label={}, shape={}];\n",
+                index,
+                quote(&label),
+                shape
+            ));
+        }
+
+        if !self.is_empty() {
+            dot.push_str(&format!("    start -> {};\n", self.entry_state));
+        }
+
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            // parallel edges are drawn as one, labeled with all their symbols
+            let mut edges: Vec<(usize, Vec<&str>)> = Vec::new();
+            for transition in state_transition {
+                let symbol = if transition.is_epsilon() {
+                    "ε"
+                } else {
+                    transition.symbol()
+                };
+
+                match edges
+                    .iter_mut()
+                    .find(|(end, _)| *end == transition.end_state())
+                {
+                    Some((_, symbols)) => symbols.push(symbol),
+                    None => edges.push((transition.end_state(), vec![symbol])),
+                }
+            }
+
+            for (end_state, symbols) in edges {
+                dot.push_str(&format!(
+                    "    {} -> {} [label={}];\n",
+                    index,
+                    end_state,
+                    quote(&symbols.join(","))
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Minimal DFA with the trap state (and every other state that can't reach
+    /// an accept state) dropped, the missing transitions just reject.
+    pub fn minimize_partial(&self) -> Self {
+        self.minimize().remove_dead()
+    }
+
+    /// Minimal complete DFA, merging the tags of the equivalent states of the
+    /// (reachable) DFA given by `to_deterministic`.
+    pub fn minimize(&self) -> Self {
+        let automata = self.to_deterministic();
+        if automata.is_empty() {
+            return automata;
+        }
+
+        let mapping: Vec<_> = automata
+            .equivalence_classes()
+            .into_iter()
+            .map(Some)
+            .collect();
+        automata.rebuild(&mapping)
+    }
+
+    /// Whether this is a complete DFA with every state reachable and no two
+    /// states equivalent, that is, whether `minimize` would leave it as it is.
+    pub fn is_minimal(&self) -> bool {
+        let known_symbols = self
+            .transitions
+            .iter()
+            .flatten()
+            .all(|t| self.alphabet.iter().any(|s| s == t.symbol()));
+        if self.is_empty()
+            || !known_symbols
+            || !self.is_deterministic()
+            || !self.is_complete()
+            || self.default_transitions.iter().any(Option::is_some)
+            || self.reachable_states().len() != self.len()
+        {
+            return false;
+        }
+
+        let classes = self.equivalence_classes();
+        classes.iter().max().map_or(0, |max| max + 1) == self.len()
+    }
+
+    /// Hopcroft's partition refinement over a complete DFA, giving each state
+    /// the class of the states equivalent to it. Classes are numbered by their
+    /// first state.
+    fn equivalence_classes(&self) -> Vec<usize> {
+        let alphabet = &self.alphabet;
+        let mut predecessors = vec![vec![Vec::new(); self.len()]; alphabet.len()];
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            for transition in state_transition {
+                let symbol = alphabet.iter().position(|s| s == transition.symbol());
+                predecessors[symbol.unwrap()][transition.end_state()].push(index);
+            }
+        }
+
+        let (accept, reject): (Vec<_>, Vec<_>) =
+            (0..self.len()).partition(|index| self.accept_states.contains(index));
+        let mut blocks: Vec<_> = vec![accept, reject]
+            .into_iter()
+            .filter(|block| !block.is_empty())
+            .collect();
+
+        let mut block_of = vec![0; self.len()];
+        for (block, states) in blocks.iter().enumerate() {
+            for &state in states {
+                block_of[state] = block;
+            }
+        }
+
+        let mut pending: Vec<_> = (0..blocks.len()).collect();
+        let mut is_pending = vec![true; blocks.len()];
+        while let Some(splitter) = pending.pop() {
+            is_pending[splitter] = false;
+            let splitter = blocks[splitter].clone();
+            for predecessors in &predecessors {
+                let mut marked = vec![false; self.len()];
+                let mut touched = Vec::new();
+                for &state in &splitter {
+                    for &predecessor in &predecessors[state] {
+                        if !marked[predecessor] {
+                            marked[predecessor] = true;
+                            if !touched.contains(&block_of[predecessor]) {
+                                touched.push(block_of[predecessor]);
+                            }
+                        }
+                    }
+                }
+
+                for block in touched {
+                    let (inside, outside): (Vec<_>, Vec<_>) =
+                        blocks[block].iter().partition(|&&state| marked[state]);
+                    if outside.is_empty() {
+                        continue;
+                    }
+
+                    let new_block = blocks.len();
+                    let smaller = if is_pending[block] || inside.len() > outside.len() {
+                        new_block
+                    } else {
+                        block
+                    };
+
+                    for &state in &outside {
+                        block_of[state] = new_block;
+                    }
+
+                    blocks[block] = inside;
+                    blocks.push(outside);
+                    is_pending.push(false);
+                    if !is_pending[smaller] {
+                        is_pending[smaller] = true;
+                        pending.push(smaller);
+                    }
+                }
+            }
+        }
+
+        let mut renumbered = vec![None; blocks.len()];
+        let mut next_index = 0;
+        let mut classes = Vec::with_capacity(self.len());
+        for &block in &block_of {
+            let new = *renumbered[block].get_or_insert_with(|| {
+                next_index += 1;
+                next_index - 1
+            });
+            classes.push(new);
+        }
+
+        classes
+    }
+
+    /// Shortest word accepted by both automata (symbols concatenated), found
+    /// by a breadth first search over pairs of state sets.
+    pub fn language_intersection_witness(&self, other: &Automata) -> Option<String> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+
+        let alphabet: Vec<_> = self
+            .alphabet
+            .iter()
+            .filter(|symbol| other.alphabet.contains(symbol))
+            .collect();
+
+        let mut start = (
+            self.e_closure_set(&[self.entry_state]),
+            other.e_closure_set(&[other.entry_state]),
+        );
+        start.0.sort_unstable();
+        start.1.sort_unstable();
+
+        let mut visited = HashSet::new();
+        visited.insert(start.clone());
+        let mut queue = VecDeque::new();
+        queue.push_back((start, Vec::new()));
+
+        while let Some(((left, right), word)) = queue.pop_front() {
+            if self.any_accept(&left) && other.any_accept(&right) {
+                return Some(word.concat());
+            }
+
+            for symbol in &alphabet {
+                let mut next = (self.step(&left, symbol), other.step(&right, symbol));
+                if next.0.is_empty() || next.1.is_empty() {
+                    continue;
+                }
+
+                next.0.sort_unstable();
+                next.1.sort_unstable();
+                if visited.insert(next.clone()) {
+                    let mut word = word.clone();
+                    word.push(symbol.as_str());
+                    queue.push_back((next, word));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Gives every state, in index order, the single tag from `names`.
+    pub fn relabel_with(&mut self, names: &[&str]) -> Result<(), AutomataError> {
+        if names.len() != self.len() {
+            return Err(AutomataError::LengthMismatch {
+                expected: self.len(),
+                found: names.len(),
+            });
+        }
+
+        let mut seen = HashSet::new();
+        for name in names {
+            if !seen.insert(name) {
+                return Err(AutomataError::DuplicateTag((*name).to_owned()));
+            }
+        }
+
+        for (state, name) in self.states.iter_mut().zip(names) {
+            *state = State::from(*name);
+        }
+
+        self.reindex_states();
+        self.forget_determinization();
+        Ok(())
+    }
+
+    fn forget_determinization(&mut self) {
+        self.provenance.clear();
+        self.accept_priorities.clear();
+        self.subset_indices.clear();
+    }
+
+    /// One row per state and one column per symbol of `order`, holding the
+    /// state the symbol leads to (the first one, for a non deterministic move).
+    pub fn transition_matrix_ordered(
+        &self,
+        order: &[&str],
+    ) -> Result<Vec<Vec<Option<usize>>>, AutomataError> {
+        if let Some(symbol) = self.alphabet.iter().find(|s| !order.contains(&s.as_str())) {
+            return Err(AutomataError::MissingSymbol(symbol.clone()));
+        }
+
+        let matrix = (0..self.len())
+            .map(|index| {
+                order
+                    .iter()
+                    .map(|symbol| self.move_from_with(index, symbol).first().cloned())
+                    .collect()
+            })
+            .collect();
+
+        Ok(matrix)
+    }
+
+    /// Whether every state is entered from at most one state on each symbol,
+    /// that is, whether reversing the transitions keeps them deterministic.
+    /// A default transition counts as one edge per symbol of the alphabet it
+    /// stands for, as in `reverse`.
+    pub fn is_reversible(&self) -> bool {
+        let mut sources = HashMap::new();
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            let mut edges: Vec<_> = state_transition
+                .iter()
+                .map(|transition| (transition.end_state(), transition.symbol()))
+                .collect();
+
+            if let Some(end_state) = self.default_transitions[index] {
+                for symbol in self.alphabet.iter().filter(|s| !s.is_empty()) {
+                    if state_transition.iter().all(|t| t.symbol() != symbol) {
+                        edges.push((end_state, symbol));
+                    }
+                }
+            }
+
+            for key in edges {
+                if *sources.entry(key).or_insert(index) != index {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Concatenation of the languages of `parts`, in order. With more than one
+    /// part each state's tags get the number of its part as prefix (`"1.q0"`)
+    /// so that tags stay unique.
+    pub fn concat_all(parts: &[Automata]) -> Self {
+        match parts {
+            [] => {
+                let mut automata = Automata::new();
+                automata.push_state("0".into());
+                automata.push_accept_state(0);
+                automata
+            }
+
+            [part] => part.clone(),
+
+            [first, rest @ ..] => {
+                let mut automata = first.tagged(0);
+                for (index, part) in rest.iter().enumerate() {
+                    automata.append_concatenated(&part.tagged(index + 1));
+                }
+
+                automata
+            }
+        }
+    }
+
+    /// Union of the languages of `parts`: a new entry tagged "^" has an epsilon
+    /// transition to each part's entry, tags are prefixed as in `concat_all`.
+    pub fn alternation(parts: &[Automata]) -> Self {
+        let mut automata = Automata::new();
+        automata.push_state("^".into());
+        for (index, part) in parts.iter().enumerate() {
+            let offset = automata.merge(&part.tagged(index));
+            if !part.is_empty() {
+                automata.push_transition_from(0, Transition::epsilon(part.entry_state + offset));
+            }
+        }
+
+        automata
+    }
+
+    /// NFA for the concatenation of both languages, built by `concat_all`:
+    /// epsilon transitions lead from the accept states of `self` to the entry
+    /// of `other`, whose accept states are the only ones left.
+    pub fn concat(&self, other: &Automata) -> Self {
+        Automata::concat_all(&[self.clone(), other.clone()])
+    }
+
+    /// NFA for the union of both languages, built as the `alternation` of the
+    /// two. The alphabets are joined: a symbol only one of them knows just has
+    /// no transitions in the other's copy.
+    pub fn union(&self, other: &Automata) -> Self {
+        Automata::alternation(&[self.clone(), other.clone()])
+    }
+
+    /// NFA for any number of repetitions of the language: a new state tagged
+    /// "^" is the entry and the only accept state, with epsilon transitions to
+    /// the old entry and back from every old accept state. The old tags get
+    /// the `0.` prefix, as in `concat_all`.
+    pub fn kleene_star(&self) -> Self {
+        let mut automata = self.tagged(0);
+        let accept_states = std::mem::take(&mut automata.accept_states);
+        automata.push_state("^".into());
+        let entry_state = automata.len() - 1;
+        if !self.is_empty() {
+            automata.push_transition_from(entry_state, Transition::epsilon(self.entry_state));
+        }
+
+        for index in accept_states {
+            automata.push_transition_from(index, Transition::epsilon(self.entry_state));
+            automata.push_transition_from(index, Transition::epsilon(entry_state));
+        }
+
+        automata.set_entry_state(entry_state);
+        automata.push_accept_state(entry_state);
+        automata
+    }
+
+    fn append_concatenated(&mut self, other: &Automata) {
+        if self.is_empty() {
+            return;
+        }
+
+        let accept_states = std::mem::take(&mut self.accept_states);
+        let offset = self.merge(other);
+        if !other.is_empty() {
+            for index in accept_states {
+                let transition = Transition::epsilon(other.entry_state + offset);
+                self.push_transition_from(index, transition);
+            }
+        }
+    }
+
+    fn tagged(&self, prefix: usize) -> Self {
+        let mut automata = self.clone();
+        for state in &mut automata.states {
+            let tags = state.tags().iter().map(|tag| format!("{}.{}", prefix, tag));
+            *state = State::from(tags.collect::<Set<_>>());
+        }
+
+        automata.reindex_states();
+        automata
+    }
+
+    /// Rebuilds the index `find` relies on, after the states were changed
+    /// in place.
+    fn reindex_states(&mut self) {
+        self.state_indices.clear();
+        for (index, state) in self.states.iter().enumerate() {
+            self.state_indices.entry(state.clone()).or_insert(index);
+        }
+    }
+
+    /// One line per state, `>` marking the entry and `*` the accept states,
+    /// like `>q0 -a-> q1, -b-> q2`.
+    pub fn transitions_summary(&self) -> String {
+        let mut summary = String::new();
+        for (index, state) in self.states.iter().enumerate() {
+            if index == self.entry_state {
+                summary.push('>');
+            }
+
+            if self.accept_states.contains(&index) {
+                summary.push('*');
+            }
+
+            summary.push_str(&state.label());
+            let edges: Vec<_> = self.transitions[index]
+                .iter()
+                .map(|t| {
+                    let symbol = if t.is_epsilon() { "ε" } else { t.symbol() };
+                    format!(" -{}-> {}", symbol, self[t.end_state()].label())
+                })
+                .collect();
+
+            summary.push_str(&edges.join(","));
+            summary.push('\n');
+        }
+
+        summary
+    }
+
+    /// Drops the states the entry can't reach, keeping the order of the rest.
+    pub fn remove_unreachable(&self) -> Self {
+        let mut mapping = vec![None; self.len()];
+        for (new, old) in self.reachable_states().into_iter().enumerate() {
+            mapping[old] = Some(new);
+        }
+
+        self.rebuild(&mapping)
+    }
+
+    /// Drops the states that can't reach an accept state (the entry is always
+    /// kept), keeping the order of the rest.
+    pub fn remove_dead(&self) -> Self {
+        let dead = self.dead_states();
+        let mut mapping = Vec::with_capacity(self.len());
+        let mut next_index = 0;
+        for index in 0..self.len() {
+            if index != self.entry_state && dead.contains(&index) {
+                mapping.push(None);
+            } else {
+                mapping.push(Some(next_index));
+                next_index += 1;
+            }
+        }
+
+        self.rebuild(&mapping)
+    }
+
+    pub fn trim(&self) -> Self {
+        self.remove_unreachable().remove_dead()
+    }
+
+    /// Whether both automata accept the same words, whatever their states.
+    /// Both are determinized over the union of their alphabets and walked in
+    /// lockstep, looking for a pair of states where only one accepts.
+    pub fn equivalent(&self, other: &Automata) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return self.is_empty_language() && other.is_empty_language();
+        }
+
+        let mut alphabet: Vec<&str> = self.alphabet.iter().map(String::as_str).collect();
+        for symbol in other.alphabet.iter() {
+            if !alphabet.contains(&symbol.as_str()) {
+                alphabet.push(symbol);
+            }
+        }
+
+        let left = self.to_deterministic_over(&alphabet).unwrap();
+        let right = other.to_deterministic_over(&alphabet).unwrap();
+        let mut visited = HashSet::new();
+        let mut stack = vec![(left.entry_state, right.entry_state)];
+        visited.insert(stack[0]);
+        while let Some((i, j)) = stack.pop() {
+            if left.accept_states.contains(&i) != right.accept_states.contains(&j) {
+                return false;
+            }
+
+            for symbol in &alphabet {
+                let pair = (
+                    left.move_from_with(i, symbol)[0],
+                    right.move_from_with(j, symbol)[0],
+                );
+                if visited.insert(pair) {
+                    stack.push(pair);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Minimal DFA accepting exactly the `sample` words, each split into
+    /// symbols of this alphabet as in `tokenize`: the union of one chain of
+    /// states per word, minimized. A word that can't be tokenized is reported
+    /// as a `MissingSymbol` error carrying it.
+    pub fn strip_to_language(&self, sample: &[&str]) -> Result<Self, AutomataError> {
+        let words = sample
+            .iter()
+            .map(|word| match self.tokenize(word) {
+                Some(symbols) => Ok(self.singleton(&symbols)),
+                None => Err(AutomataError::MissingSymbol((*word).to_owned())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut automata = Automata::alternation(&words);
+        automata.alphabet = self.alphabet.clone();
+        Ok(automata.minimize())
+    }
+
+    fn singleton(&self, word: &[&str]) -> Self {
+        let mut automata = Automata::new();
+        automata.alphabet = self.alphabet.clone();
+        automata.push_state("0".into());
+        for (index, symbol) in word.iter().enumerate() {
+            automata.push_state((index + 1).to_string().into());
+            automata.push_transition_from(index, Transition::new(symbol, index + 1));
+        }
+
+        automata.push_accept_state(word.len());
+        automata
+    }
+
+    /// Equivalent NFA without epsilon transitions and with the same states:
+    /// each state moves on a symbol wherever its epsilon closure did, and
+    /// accepts if its closure holds an accept state. Default transitions are
+    /// only followed for the symbols of the alphabet.
+    pub fn remove_epsilon(&self) -> Self {
+        let mut automata = Automata::new();
+        automata.alphabet = self.alphabet.clone();
+        for state in &self.states {
+            automata.push_state(state.clone());
+        }
+
+        if self.is_empty() {
+            return automata;
+        }
+
+        automata.set_entry_state(self.entry_state);
+        for index in 0..self.len() {
+            let e_closure_set = self.e_closure_set(&[index]);
+            if self.any_accept(&e_closure_set) {
+                automata.push_accept_state(index);
+            }
+
+            for symbol in self.alphabet.iter() {
+                let mut end_states: Vec<_> = e_closure_set
+                    .iter()
+                    .flat_map(|&state| self.move_from_with(state, symbol))
+                    .collect();
+                end_states.sort_unstable();
+                end_states.dedup();
+                for end_state in end_states {
+                    automata.push_transition_from(index, Transition::new(symbol, end_state));
+                }
+            }
+        }
+
+        automata
+    }
+
+    /// Every accepted word of at most `max_len` symbols, shortest first and
+    /// then in lexicographic order of their symbols.
+    pub fn words_up_to(&self, max_len: usize) -> Vec<Vec<String>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let mut alphabet: Vec<_> = self.alphabet.iter().filter(|s| !s.is_empty()).collect();
+        alphabet.sort();
+        alphabet.dedup();
+
+        let mut words = Vec::new();
+        let mut frontier = vec![(Vec::new(), self.e_closure_set(&[self.entry_state]))];
+        for len in 0..=max_len {
+            let mut next_frontier = Vec::new();
+            for (word, current) in frontier {
+                if self.any_accept(&current) {
+                    words.push(word.clone());
+                }
+
+                if len == max_len {
+                    continue;
+                }
+
+                for symbol in &alphabet {
+                    let next = self.step(&current, symbol);
+                    if !next.is_empty() {
+                        let mut next_word = word.clone();
+                        next_word.push((*symbol).clone());
+                        next_frontier.push((next_word, next));
+                    }
+                }
+            }
+
+            frontier = next_frontier;
+        }
+
+        words
+    }
+
+    /// Number of accepted words, `None` if there are infinitely many of them.
+    /// Counts the paths of the trimmed DFA, saturating at `u64::MAX`.
+    pub fn language_size(&self) -> Option<u64> {
+        let afd_automata = self.to_deterministic().trim();
+        if afd_automata.is_empty_language() {
+            return Some(0);
+        }
+
+        let order = afd_automata.topological_order()?;
+        let mut counts = vec![0u64; afd_automata.len()];
+        for &index in order.iter().rev() {
+            let mut count = afd_automata.accept_states.contains(&index) as u64;
+            for transition in afd_automata.transitions_from(index) {
+                count = count.saturating_add(counts[transition.end_state()]);
+            }
+
+            counts[index] = count;
+        }
+
+        Some(counts[afd_automata.entry_state])
+    }
+
+    /// Whether the automaton accepts finitely many words: no cycle is left once
+    /// the epsilon transitions are removed (so an epsilon cycle doesn't count)
+    /// and the automaton is trimmed.
+    pub fn is_finite_language(&self) -> bool {
+        if self.is_empty_language() {
+            return true;
+        }
+
+        self.remove_epsilon().trim().topological_order().is_some()
+    }
+
+    /// States reachable from the entry, sorted so that every transition leads
+    /// forward, or `None` if they're part of a cycle.
+    fn topological_order(&self) -> Option<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Color {
+            White,
+            Gray,
+            Black,
+        }
+
+        if self.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let mut color = vec![Color::White; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![(self.entry_state, 0)];
+        color[self.entry_state] = Color::Gray;
+        while let Some(&mut (index, ref mut next)) = stack.last_mut() {
+            match self.transitions[index].get(*next) {
+                Some(transition) => {
+                    *next += 1;
+                    let end_state = transition.end_state();
+                    match color[end_state] {
+                        Color::White => {
+                            color[end_state] = Color::Gray;
+                            stack.push((end_state, 0));
+                        }
+                        Color::Gray => return None,
+                        Color::Black => {}
+                    }
+                }
+
+                None => {
+                    color[index] = Color::Black;
+                    order.push(index);
+                    stack.pop();
+                }
+            }
+        }
+
+        order.reverse();
+        Some(order)
+    }
+
+    /// One of the shortest accepted words (the first in alphabet order), or
+    /// `None` if the language is empty.
+    pub fn shortest_accepted(&self) -> Option<Vec<String>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut start = self.e_closure_set(&[self.entry_state]);
+        start.sort_unstable();
+
+        let mut visited = HashSet::new();
+        visited.insert(start.clone());
+        let mut queue = VecDeque::new();
+        queue.push_back((start, Vec::new()));
+
+        while let Some((current, word)) = queue.pop_front() {
+            if self.any_accept(&current) {
+                return Some(word);
+            }
+
+            for symbol in self.alphabet.iter() {
+                let mut next = self.step(&current, symbol);
+                if next.is_empty() {
+                    continue;
+                }
+
+                next.sort_unstable();
+                if visited.insert(next.clone()) {
+                    let mut word = word.clone();
+                    word.push(symbol.clone());
+                    queue.push_back((next, word));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Regular expression for the language, through state elimination over
+    /// the trimmed minimal DFA. It uses `|`, `*` and parentheses like
+    /// `from_regex`, symbols are written as they are and the empty language
+    /// gives `∅`.
+    pub fn to_regex(&self) -> String {
+        if self.is_empty_language() {
+            return "∅".to_owned();
+        }
+
+        // the DFA states, then a new entry and a new single accept state
+        let afd_automata = self.minimize().trim();
+        let len = afd_automata.len();
+        let (start, end) = (len, len + 1);
+        let mut edges: Vec<Vec<Option<Expr>>> = vec![vec![None; len + 2]; len + 2];
+        let add_edge = |edges: &mut Vec<Vec<Option<Expr>>>, from: usize, to: usize, expr| {
+            edges[from][to] = Some(match edges[from][to].take() {
+                Some(current) => current.alt(expr),
+                None => expr,
+            });
+        };
+
+        add_edge(&mut edges, start, afd_automata.entry_state, Expr::Epsilon);
+        for &index in &afd_automata.accept_states {
+            add_edge(&mut edges, index, end, Expr::Epsilon);
+        }
+
+        for (index, state_transition) in afd_automata.transitions.iter().enumerate() {
+            for transition in state_transition {
+                let symbol = Expr::Symbol(transition.symbol().to_owned());
+                add_edge(&mut edges, index, transition.end_state(), symbol);
+            }
+        }
+
+        for removed in 0..len {
+            let repeated = edges[removed][removed].take().map(Expr::star);
+            let incoming: Vec<_> = (0..len + 2)
+                .filter_map(|from| edges[from][removed].take().map(|expr| (from, expr)))
+                .collect();
+            let outgoing: Vec<_> = (0..len + 2)
+                .filter_map(|to| edges[removed][to].take().map(|expr| (to, expr)))
+                .collect();
+
+            for (from, before) in &incoming {
+                for (to, after) in &outgoing {
+                    let mut expr = before.clone();
+                    if let Some(repeated) = &repeated {
+                        expr = expr.concat(repeated.clone());
+                    }
+
+                    add_edge(&mut edges, *from, *to, expr.concat(after.clone()));
+                }
+            }
+        }
+
+        edges[start][end]
+            .take()
+            .map_or_else(String::new, |expr| expr.to_string())
+    }
+
+    /// Minimal DFA for the language with its states tagged by their index,
+    /// going through `trim`, `remove_epsilon`, `to_deterministic` and
+    /// `minimize`. Debug builds check the language after every stage.
+    pub fn canonical_dfa(&self) -> Self {
+        if self.is_empty() {
+            return self.to_deterministic();
+        }
+
+        let trimmed = self.trim();
+        debug_assert!(self.equivalent(&trimmed), "trim changed the language");
+        let epsilon_free = trimmed.remove_epsilon();
+        debug_assert!(
+            self.equivalent(&epsilon_free),
+            "remove_epsilon changed the language"
+        );
+        let afd_automata = epsilon_free.to_deterministic();
+        debug_assert!(
+            self.equivalent(&afd_automata),
+            "to_deterministic changed the language"
+        );
+        let mut minimal = afd_automata.minimize();
+        debug_assert!(self.equivalent(&minimal), "minimize changed the language");
+
+        let names: Vec<_> = (0..minimal.len()).map(|index| index.to_string()).collect();
+        let names: Vec<_> = names.iter().map(String::as_str).collect();
+        minimal.relabel_with(&names).unwrap();
+        minimal
+    }
+
+    /// Merges every cycle of epsilon transitions into one state: states that
+    /// reach each other through epsilon moves accept the same words. States
+    /// with a default transition are left apart, the merged state could only
+    /// keep one of them.
+    pub fn collapse_epsilon_cycles(&self) -> Self {
+        if self.is_empty() {
+            return self.clone();
+        }
+
+        let closures: Vec<_> = (0..self.len())
+            .map(|index| self.e_closure_set(&[index]))
+            .collect();
+
+        let mut mapping: Vec<Option<usize>> = Vec::with_capacity(self.len());
+        let mut next_index = 0;
+        for index in 0..self.len() {
+            let representative = match self.default_transitions[index] {
+                Some(_) => None,
+                None => (0..index).find(|&other| {
+                    self.default_transitions[other].is_none()
+                        && closures[index].contains(&other)
+                        && closures[other].contains(&index)
+                }),
+            };
+
+            match representative {
+                Some(representative) => mapping.push(mapping[representative]),
+                None => {
+                    mapping.push(Some(next_index));
+                    next_index += 1;
+                }
+            }
+        }
+
+        let mut automata = self.rebuild(&mapping);
+        automata.remove_epsilon_self_loops();
+        automata
+    }
+
+    /// Same language as `to_deterministic`, minimized, but cutting down the
+    /// NFA first through `collapse_epsilon_cycles` and `remove_epsilon`, so
+    /// there are fewer states to build subsets from. Debug builds check the
+    /// language of the result.
+    pub fn to_deterministic_optimized(&self) -> Self {
+        let automata = self
+            .collapse_epsilon_cycles()
+            .remove_epsilon()
+            .to_deterministic()
+            .minimize();
+
+        debug_assert!(
+            automata.equivalent(self),
+            "to_deterministic_optimized changed the language"
+        );
+        automata
+    }
+}
+
+impl fmt::Display for Automata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Estados")?;
+        for (index, state) in self.states.iter().enumerate() {
+            write!(f, "{} = {{", index)?;
+            for tag in state.tags() {
+                write!(f, " {}", tag)?;
+            }
+            writeln!(f, " }}")?;
+        }
+
+        // the parser takes state 0 as the entry unless told otherwise
+        if self.entry_state != 0 {
+            writeln!(f, "\nEstado inicial\n{}", self.entry_state)?;
+        }
+
+        writeln!(f, "\nEstados de aceptación")?;
+        let mut accept_states = self.accept_states.clone();
+        accept_states.sort_unstable();
+        for index in accept_states {
+            write!(f, "{} ", index)?;
+        }
+
+        writeln!(f, "\n\nAlfabeto")?;
+        for symbol in self.alphabet.iter() {
+            write!(f, "{} ", escape_symbol(symbol))?;
+        }
+
+        writeln!(f, "\n\nTransiciones")?;
+        for (index, state_transition) in self.transitions.iter().enumerate() {
+            for transition in state_transition {
+                let symbol = if transition.symbol() == "" {
+                    "-1".to_owned()
+                } else {
+                    escape_symbol(transition.symbol())
+                };
+
+                writeln!(f, "{} {} {}", index, symbol, transition.end_state())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_symbol(symbol: &str) -> Result<(), AutomataError> {
+    if symbol.is_empty() || symbol == "-1" {
+        return Err(AutomataError::EpsilonSymbol(symbol.to_owned()));
+    }
+
+    Ok(())
+}
+
+fn check_alphabet(alphabet: &[String]) {
+    for symbol in alphabet {
+        if let Err(error) = check_symbol(symbol) {
+            panic!("Invalid alphabet: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{AutomataParser, FileParser};
+
+    fn walk(automata: &Automata, word: &[&str]) -> bool {
+        let mut state = automata.entry_state;
+        for symbol in word {
+            match automata.move_from_with(state, symbol).first() {
+                Some(&next) => state = next,
+                None => return false,
+            }
+        }
+
+        automata.accept_states.contains(&state)
+    }
+
+    fn words(alphabet: &[&'static str], max_len: usize) -> Vec<Vec<&'static str>> {
+        let mut words = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..max_len {
+            let mut next = Vec::new();
+            for word in &last {
+                for symbol in alphabet {
+                    let mut word: Vec<_> = word.clone();
+                    word.push(*symbol);
+                    next.push(word);
+                }
+            }
+
+            words.extend(next.iter().cloned());
+            last = next;
+        }
+
+        words
+    }
+
+    /// The automaton of `e_closure_set`, for the tests built on it.
+    fn epsilon_example() -> Automata {
+        let mut automata = Automata::new();
+        for index in 0..6 {
+            automata.push_state(index.to_string().into());
+        }
+
+        automata.push_accept_state(5);
+
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("", 2));
+        automata.push_transition_from(0, Transition::new("", 3));
+        automata.push_transition_from(1, Transition::new("", 3));
+        automata.push_transition_from(2, Transition::new("b", 3));
+        automata.push_transition_from(3, Transition::new("", 4));
+        automata.push_transition_from(4, Transition::new("a", 5));
+        automata
+    }
+
+    #[test]
+    fn e_closure_set() {
+        let mut automata = Automata::new();
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_state("3".into());
+        automata.push_state("4".into());
+        automata.push_state("5".into());
+
+        automata.push_accept_state(5);
+
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("", 2));
+        automata.push_transition_from(0, Transition::new("", 3));
+        automata.push_transition_from(1, Transition::new("", 3));
+        automata.push_transition_from(2, Transition::new("b", 3));
+        automata.push_transition_from(3, Transition::new("", 4));
+        automata.push_transition_from(4, Transition::new("a", 5));
+
+        let set_01 = automata.e_closure_set(&[0, 1]);
+        let set_345 = automata.e_closure_set(&[3, 4, 5]);
+
+        assert_eq!(&set_01, &[0, 1, 3, 4, 2]);
+        assert_eq!(&set_345, &[3, 4, 5]);
+    }
+
+    #[test]
+    fn epsilon_closure_single() {
+        let automata = epsilon_example();
+        for index in 0..automata.len() {
+            assert_eq!(
+                automata.epsilon_closure_single(index),
+                automata.e_closure_set(&[index])
+            );
+        }
+
+        assert_eq!(automata.epsilon_closure_single(0), vec![0, 2, 3, 4]);
+        let state: State = "2".into();
+        assert_eq!(automata.epsilon_closure_single(state), vec![2]);
+    }
+
+    #[test]
+    fn has_epsilon() {
+        let automata = epsilon_example();
+        assert!(automata.has_epsilon());
+        assert!(automata.is_nfa());
+    }
+
+    #[test]
+    fn remove_epsilon() {
+        let mut automata = epsilon_example();
+        // the language is a|ba|aa, also by the alphabet
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        let epsilon_free = automata.remove_epsilon();
+        assert!(!epsilon_free.has_epsilon());
+        assert_eq!(epsilon_free.len(), automata.len());
+        assert_eq!(epsilon_free.accept_states, vec![5]);
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(epsilon_free.accepts(&word), automata.accepts(&word));
+        }
+
+        assert!(epsilon_free.accepts(&["b", "a"]));
+        assert!(epsilon_free.equivalent(&automata));
+    }
+
+    #[test]
+    fn find_state() {
+        let mut automata = Automata::new();
+        automata.push_state("p".into());
+        automata.push_state("q".into());
+        automata.push_state("r".into());
+
+        assert_eq!(Some(0), automata.find(&"p".into()));
+        assert_eq!(Some(1), automata.find(&"q".into()));
+        assert_eq!(Some(2), automata.find(&"r".into()));
+        assert_eq!(None, automata.find(&"s".into()));
+
+        automata.push_state("p".into());
+        assert_eq!(Some(0), automata.find(&"p".into()));
+
+        automata.relabel_with(&["a", "b", "c", "d"]).unwrap();
+        assert_eq!(Some(3), automata.find(&"d".into()));
+        assert_eq!(None, automata.find(&"p".into()));
+        assert_eq!(Some(1), automata.tagged(7).find(&"7.b".into()));
+    }
+
+    #[test]
+    fn find_state_large() {
+        // states i -> i and i -> i + 1 on "a", i -> i on "b", once added by
+        // index and once by state, which goes through `find`
+        let n = 200;
+        let mut automata = Automata::new();
+        let mut by_state = Automata::new();
+        for automata in &mut [&mut automata, &mut by_state] {
+            automata.push_symbol("a");
+            automata.push_symbol("b");
+            for index in 0..n {
+                automata.push_state(format!("q{}", index).into());
+            }
+        }
+
+        let state = |index: usize| State::from(format!("q{}", index).as_str());
+        for index in 0..n - 1 {
+            let edges = [("a", index), ("a", index + 1), ("b", index)];
+            for &(symbol, end_state) in &edges {
+                automata.push_transition_from(index, Transition::new(symbol, end_state));
+                by_state.push_transition_from(state(index), Transition::new(symbol, end_state));
+            }
+        }
+
+        automata.push_accept_state(n - 1);
+        by_state.push_accept_state(state(n - 1));
+        for index in 0..n {
+            assert_eq!(automata.find(&state(index)), Some(index));
+        }
+
+        assert_eq!(format!("{}", by_state), format!("{}", automata));
+
+        let afd_automata = automata.to_deterministic();
+        assert_eq!(
+            format!("{}", by_state.to_deterministic()),
+            format!("{}", afd_automata)
+        );
+        assert_eq!(afd_automata.len(), n);
+        for index in 0..afd_automata.len() {
+            assert_eq!(afd_automata.find(&afd_automata[index].clone()), Some(index));
+        }
+    }
+
+    #[test]
+    fn product_with_labels() {
+        let mut left = Automata::new();
+        left.push_symbol("a");
+        left.push_symbol("b");
+        left.push_state("p".into());
+        left.push_state("q".into());
+        left.push_accept_state(1);
+        left.push_transition_from(0, Transition::new("a", 1));
+        left.push_transition_from(0, Transition::new("b", 0));
+        left.push_transition_from(1, Transition::new("a", 0));
+        left.push_transition_from(1, Transition::new("b", 1));
+
+        let mut right = Automata::new();
+        right.push_symbol("a");
+        right.push_symbol("b");
+        right.push_state("x".into());
+        right.push_state("y".into());
+        right.push_accept_state(1);
+        right.push_transition_from(0, Transition::new("a", 1));
+        right.push_transition_from(0, Transition::new("b", 0));
+        right.push_transition_from(1, Transition::new("a", 1));
+        right.push_transition_from(1, Transition::new("b", 0));
+
+        let (product, pairs) = left.product_with_labels(&right);
+
+        assert_eq!(&pairs, &[(0, 0), (1, 1), (0, 1), (1, 0)]);
+        assert_eq!(&product.accept_states, &[1]);
+        assert_eq!(Some(2), product.find(&"(p,y)".into()));
+        assert_eq!(product.move_from_with(1, "b"), vec![3]);
+    }
+
+    #[test]
+    fn collapse_equivalent_accept_states() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_accept_state(1);
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("b", 2));
+
+        let collapsed = automata.collapse_equivalent_accept_states();
+
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(&collapsed.accept_states, &[1]);
+        assert_eq!(Some(1), collapsed.find(&(&["1", "2"][..]).into()));
+        assert_eq!(collapsed.move_from_with(0, "a"), vec![1]);
+        assert_eq!(collapsed.move_from_with(0, "b"), vec![1]);
+
+        // a default transition out of 1 only, "az" reaches 1 again while "bz"
+        // gets stuck
+        automata.set_default_transition(1, 0);
+        automata.push_transition_from(0, Transition::new("z", 1));
+        let collapsed = automata.collapse_equivalent_accept_states();
+        assert_eq!(collapsed.len(), 3);
+        for word in &[&["a", "z"][..], &["b", "z"], &["a", "z", "z"]] {
+            assert_eq!(collapsed.accepts(word), automata.accepts(word));
+        }
+
+        // both loop on anything else, which is the same
+        automata.set_default_transition(1, 1);
+        automata.set_default_transition(2, 2);
+        let collapsed = automata.collapse_equivalent_accept_states();
+        assert_eq!(collapsed.len(), 2);
+        assert_eq!(collapsed.default_transition(1), Some(1));
+        assert!(collapsed.accepts(&["b", "c", "d"]));
+    }
+
+    #[test]
+    fn to_deterministic_budgeted() {
+        let automata = FileParser::with_filename("tests/afn3").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+
+        for budget in 1..afd_automata.len() {
+            let (partial, complete) = automata.to_deterministic_budgeted(budget);
+            assert!(!complete);
+            assert!(partial.len() <= budget);
+
+            for word in words(&["0", "1"], 6) {
+                if walk(&partial, &word) {
+                    assert!(walk(&afd_automata, &word));
+                }
+            }
+        }
+
+        let (full, complete) = automata.to_deterministic_budgeted(afd_automata.len());
+        assert!(complete);
+        assert_eq!(format!("{}", full), format!("{}", afd_automata));
+    }
+
+    #[test]
+    fn insert_alphabet_symbol_at() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("c");
+
+        assert_eq!(Ok(()), automata.insert_alphabet_symbol_at(1, "b"));
+        assert_eq!(Ok(()), automata.insert_alphabet_symbol_at(3, "d"));
+        assert_eq!(*automata.alphabet, ["a", "b", "c", "d"]);
+
+        assert_eq!(
+            Err(AutomataError::DuplicateSymbol("c".to_owned())),
+            automata.insert_alphabet_symbol_at(0, "c")
+        );
+        assert_eq!(
+            Err(AutomataError::IndexOutOfBounds { index: 5, len: 4 }),
+            automata.insert_alphabet_symbol_at(5, "e")
+        );
+        assert_eq!(*automata.alphabet, ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn reachable_accept_states() {
+        let mut automata = Automata::new();
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_state("3".into());
+        automata.push_accept_state(3);
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("", 1));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        automata.push_transition_from(3, Transition::new("a", 0));
+
+        assert_eq!(automata.reachable_states(), vec![0, 1, 2]);
+        assert_eq!(automata.reachable_accept_states(), vec![2]);
+    }
+
+    #[test]
+    fn to_deterministic_golden() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for state in &["0", "1", "2", "3", "4"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_accept_state(3);
+        automata.push_accept_state(4);
+        automata.push_transition_from(0, Transition::new("b", 2));
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("a", 3));
+        automata.push_transition_from(2, Transition::new("a", 4));
+
+        let expected = "Estados\n\
+                        0 = { 0 }\n\
+                        1 = { 1 }\n\
+                        2 = { 2 }\n\
+                        3 = { 3 }\n\
+                        4 = { 4 }\n\
+                        5 = { ! }\n\
+                        \n\
+                        Estados de aceptación\n\
+                        3 4 \n\
+                        \n\
+                        Alfabeto\n\
+                        a b \n\
+                        \n\
+                        Transiciones\n\
+                        0 a 1\n\
+                        0 b 2\n\
+                        1 a 3\n\
+                        1 b 5\n\
+                        2 a 4\n\
+                        2 b 5\n\
+                        3 a 5\n\
+                        3 b 5\n\
+                        4 a 5\n\
+                        4 b 5\n\
+                        5 a 5\n\
+                        5 b 5\n";
+
+        assert_eq!(format!("{}", automata.to_deterministic()), expected);
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert_eq!(
+            format!("{}", automata.to_deterministic()),
+            include_str!("../tests/afd1")
+        );
+    }
+
+    #[test]
+    fn accepts_with_eof() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("$");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("$", 2));
+
+        assert!(automata.accepts_with_eof(&["a"], "$"));
+        assert!(automata.accepts_with_eof(&["a", "a", "a"], "$"));
+        assert!(!automata.accepts_with_eof(&[], "$"));
+        assert!(!automata.accepts(&["a"]));
+        assert!(automata.accepts(&["a", "$"]));
+    }
+
+    #[test]
+    fn to_deterministic_large() {
+        // (a|b)* a (a|b)^n, whose minimal DFA needs 2^(n + 1) states
+        let n = 9;
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..n + 2 {
+            automata.push_state(index.to_string().into());
+        }
+
+        automata.push_accept_state(n + 1);
+        automata.push_transition_from(0, Transition::new("a", 0));
+        automata.push_transition_from(0, Transition::new("b", 0));
+        automata.push_transition_from(0, Transition::new("a", 1));
+        for index in 1..n + 1 {
+            automata.push_transition_from(index, Transition::new("a", index + 1));
+            automata.push_transition_from(index, Transition::new("b", index + 1));
+        }
+
+        let afd_automata = automata.to_deterministic();
+        assert_eq!(afd_automata.len(), 1 << (n + 1));
+        assert!(afd_automata.is_deterministic());
+    }
+
+    #[test]
+    fn accept_states_tags() {
+        let mut automata = Automata::new();
+        automata.push_state("q0".into());
+        automata.push_state((&["q1", "q2"][..]).into());
+        automata.push_state("q3".into());
+        automata.push_accept_state(2);
+        automata.push_accept_state(1);
+
+        let q1_q2: Set<_> = vec!["q1".to_owned(), "q2".to_owned()].into_iter().collect();
+        let q3: Set<_> = vec!["q3".to_owned()].into_iter().collect();
+        assert_eq!(automata.accept_states_tags(), vec![&q3, &q1_q2]);
+    }
+
+    #[test]
+    fn to_deterministic_over() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+
+        let afd_automata = automata.to_deterministic_over(&["a", "b"]).unwrap();
+        let trap = afd_automata.find(&"!".into()).unwrap();
+
+        assert_eq!(*afd_automata.alphabet, ["a", "b"]);
+        assert_eq!(afd_automata.move_from_with(0, "a"), vec![1]);
+        assert_eq!(afd_automata.move_from_with(0, "b"), vec![trap]);
+        assert_eq!(afd_automata.move_from_with(1, "b"), vec![trap]);
+        assert_eq!(afd_automata.move_from_with(trap, "b"), vec![trap]);
+        assert!(afd_automata.accepts(&["a"]));
+        assert!(!afd_automata.accepts(&["b"]));
+    }
+
+    #[test]
+    fn to_deterministic_over_subset() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+
+        assert_eq!(
+            automata.to_deterministic_over(&["b"]).unwrap_err(),
+            AutomataError::MissingSymbol("a".to_owned())
+        );
+        assert_eq!(
+            automata.to_deterministic_over(&["a", "-1"]).unwrap_err(),
+            AutomataError::EpsilonSymbol("-1".to_owned())
+        );
+    }
+
+    #[test]
+    fn to_deterministic_with() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+
+        let partial = automata.to_deterministic_with(TrapPolicy::None);
+        assert_eq!(partial.len(), 4);
+        for state in 0..partial.len() {
+            assert!(partial.move_from_with(state, "a").len() <= 1);
+            assert!(partial.move_from_with(state, "b").len() <= 1);
+        }
+        assert!(partial.find(&"!".into()).is_none());
+        assert!(partial.move_from_with(1, "b").is_empty());
+        assert!(partial.accepts(&["a", "a"]));
+        assert!(!partial.accepts(&["a", "b"]));
+
+        let unnamed = automata.to_deterministic_with(TrapPolicy::Unnamed);
+        assert_eq!(unnamed.len(), 5);
+        assert_eq!(unnamed.find(&"!".into()), Some(4));
+        assert_eq!(
+            format!("{}", unnamed),
+            format!("{}", automata.to_deterministic())
+        );
+
+        let named = automata.to_deterministic_with(TrapPolicy::Named("sink".to_owned()));
+        assert_eq!(named.len(), 5);
+        assert!(named.find(&"!".into()).is_none());
+        assert_eq!(named.find(&"sink".into()), Some(4));
+        assert_eq!(named.move_from_with(1, "b"), vec![4]);
+        assert_eq!(named.move_from_with(4, "a"), vec![4]);
+        assert!(!named.accepts(&["a", "b"]));
+    }
+
+    #[test]
+    fn accept_by_tag_suffix() {
+        let mut automata = Automata::new();
+        automata.push_state("start".into());
+        automata.push_state("a_final".into());
+        automata.push_state("b".into());
+        automata.push_state("b_final".into());
+        automata.push_state((&["c_final", "d"][..]).into());
+        automata.push_accept_state(3);
+
+        automata.accept_by_tag_suffix("_final");
+        assert_eq!(&automata.accept_states, &[3, 1]);
+
+        automata.accept_by_tag_prefix("st");
+        assert_eq!(&automata.accept_states, &[3, 1, 0]);
+    }
+
+    #[test]
+    fn transition_relation() {
+        let mut left = Automata::new();
+        let mut right = Automata::new();
+        for automata in [&mut left, &mut right].iter_mut() {
+            automata.push_state("0".into());
+            automata.push_state("1".into());
+        }
+
+        left.push_transition_from(0, Transition::new("a", 1));
+        left.push_transition_from(0, Transition::new("", 0));
+        left.push_transition_from(1, Transition::new("b", 0));
+
+        right.push_transition_from(1, Transition::new("b", 0));
+        right.push_transition_from(0, Transition::new("", 0));
+        right.push_transition_from(0, Transition::new("a", 1));
+
+        let relation = left.transition_relation();
+        assert_eq!(relation, right.transition_relation());
+        assert_eq!(
+            relation.into_iter().collect::<Vec<_>>(),
+            vec![
+                (0, "".to_owned(), 0),
+                (0, "a".to_owned(), 1),
+                (1, "b".to_owned(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn default_transition() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.set_default_transition(0, 2);
+        automata.set_default_transition(2, 0);
+
+        assert_eq!(automata.move_from_with(0, "a"), vec![1]);
+        assert_eq!(automata.move_from_with(0, "ñ"), vec![2]);
+        assert_eq!(automata.move_from_with(0, ""), Vec::<usize>::new());
+        assert!(automata.accepts(&["z", "y", "a"]));
+        assert!(!automata.accepts(&["a", "z"]));
+
+        automata.complete_with_default_trap();
+        let trap = automata.find(&"!".into()).unwrap();
+
+        assert_eq!(automata.default_transition(0), Some(2));
+        assert_eq!(automata.default_transition(1), Some(trap));
+        assert_eq!(automata.default_transition(trap), Some(trap));
+        assert_eq!(automata.move_from_with(1, "€"), vec![trap]);
+        assert!(automata.reachable_states().contains(&trap));
+    }
+
+    #[test]
+    fn is_trim() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 0));
+
+        assert!(automata.is_trim());
+        assert!(automata.dead_states().is_empty());
+
+        let afd_automata = automata.to_deterministic();
+        let trap = afd_automata.find(&"!".into()).unwrap();
+
+        assert!(!afd_automata.is_trim());
+        assert_eq!(afd_automata.dead_states(), vec![trap]);
+    }
+
+    #[test]
+    fn to_deterministic_partial_dfa() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for state in &["p", "q", "r", "unreachable"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("b", 2));
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 0));
+        automata.push_transition_from(3, Transition::new("a", 0));
+
+        assert!(automata.relabel_partial_dfa(&automata.alphabet).is_some());
+
+        let mut stats = DeterminizeStats::default();
+        let (mut expected, complete) =
+            automata.subset_construction(&automata.alphabet, usize::MAX, &mut stats);
+        assert!(complete);
+        expected.complete_with_trap();
+
+        let afd_automata = automata.to_deterministic();
+        assert_eq!(format!("{}", afd_automata), format!("{}", expected));
+        for word in words(&["a", "b"], 5) {
+            assert_eq!(automata.accepts(&word), afd_automata.accepts(&word));
+        }
+
+        automata.push_transition_from(1, Transition::new("b", 2));
+        assert!(automata.relabel_partial_dfa(&automata.alphabet).is_none());
+    }
+
+    #[test]
+    fn accept_count_along() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        for state in &["0", "1", "2", "3"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_accept_state(1);
+        automata.push_accept_state(2);
+        automata.push_accept_state(3);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        automata.push_transition_from(2, Transition::new("a", 3));
+
+        assert_eq!(automata.accept_count_along(&[]), 0);
+        assert_eq!(automata.accept_count_along(&["a", "a"]), 2);
+        assert_eq!(automata.accept_count_along(&["a", "a", "a"]), 3);
+        assert_eq!(automata.accept_count_along(&["a", "a", "a", "a", "a"]), 3);
+
+        automata.push_accept_state(0);
+        assert_eq!(automata.accept_count_along(&["a", "a", "a"]), 4);
+    }
+
+    #[test]
+    fn remove_epsilon_self_loops() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::epsilon(0));
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::epsilon(1));
+        automata.push_transition_from(1, Transition::epsilon(0));
+
+        let original = automata.clone();
+        automata.remove_epsilon_self_loops();
+
+        assert_eq!(automata.transitions_from(0), &[Transition::new("a", 1)]);
+        assert_eq!(automata.transitions_from(1), &[Transition::epsilon(0)]);
+        for word in words(&["a"], 4) {
+            assert_eq!(automata.accepts(&word), original.accepts(&word));
+        }
+
+        assert_eq!(
+            format!("{}", automata.to_deterministic()),
+            format!("{}", original.to_deterministic())
+        );
+    }
+
+    #[test]
+    fn bisimulation_equivalent() {
+        // a(b|c)
+        let mut left = Automata::new();
+        for state in &["0", "1", "2", "3"] {
+            left.push_state((*state).into());
+        }
+
+        left.push_accept_state(2);
+        left.push_accept_state(3);
+        left.push_transition_from(0, Transition::new("a", 1));
+        left.push_transition_from(1, Transition::new("b", 2));
+        left.push_transition_from(1, Transition::new("c", 3));
+
+        // ab|ac
+        let mut right = Automata::new();
+        for state in &["0", "1", "2", "3", "4"] {
+            right.push_state((*state).into());
+        }
+
+        right.push_accept_state(3);
+        right.push_accept_state(4);
+        right.push_transition_from(0, Transition::new("a", 1));
+        right.push_transition_from(0, Transition::new("a", 2));
+        right.push_transition_from(1, Transition::new("b", 3));
+        right.push_transition_from(2, Transition::new("c", 4));
+
+        for word in words(&["a", "b", "c"], 3) {
+            assert_eq!(left.accepts(&word), right.accepts(&word));
+        }
+
+        assert!(!left.bisimulation_equivalent(&right));
+        assert!(left.bisimulation_equivalent(&left.clone()));
+
+        let mut single = Automata::new();
+        single.push_state("0".into());
+        single.push_accept_state(0);
+        single.push_transition_from(0, Transition::new("a", 0));
+
+        let mut double = Automata::new();
+        double.push_state("0".into());
+        double.push_state("1".into());
+        double.push_accept_state(0);
+        double.push_accept_state(1);
+        double.push_transition_from(0, Transition::new("a", 1));
+        double.push_transition_from(1, Transition::new("a", 0));
+
+        assert!(single.bisimulation_equivalent(&double));
+
+        // p reads any unknown symbol into its accept state, q doesn't
+        let mut p = Automata::new();
+        p.push_symbol("a");
+        p.push_state("0".into());
+        p.push_state("1".into());
+        p.push_accept_state(1);
+        p.push_transition_from(0, Transition::new("a", 1));
+        let q = p.clone();
+        p.set_default_transition(0, 1);
+
+        assert_ne!(p.accepts(&["z"]), q.accepts(&["z"]));
+        assert!(!p.bisimulation_equivalent(&q));
+        assert!(p.bisimulation_equivalent(&p.clone()));
+    }
+
+    #[test]
+    fn accepting_run() {
+        let mut automata = Automata::new();
+        for state in &["0", "1", "2", "3", "4", "5"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_accept_state(5);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("a", 2));
+        automata.push_transition_from(1, Transition::new("b", 3));
+        automata.push_transition_from(2, Transition::new("b", 4));
+        automata.push_transition_from(3, Transition::epsilon(3));
+        automata.push_transition_from(4, Transition::epsilon(5));
+
+        assert_eq!(automata.accepting_run(&["a", "b"]), Some(vec![0, 2, 4, 5]));
+        assert_eq!(automata.accepting_run(&["a"]), None);
+        assert_eq!(automata.accepting_run(&["a", "b", "b"]), None);
+
+        automata.push_accept_state(0);
+        assert_eq!(automata.accepting_run(&[]), Some(vec![0]));
+    }
+
+    #[test]
+    fn symbol_sort() {
+        let mut automata = Automata::new();
+        automata.push_symbol("c");
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("c", 1));
+        automata.push_transition_from(1, Transition::new("a", 0));
+
+        let original = automata.clone();
+        automata.symbol_sort();
+
+        assert_eq!(*automata.alphabet, ["a", "b", "c"]);
+        for word in words(&["a", "b", "c"], 4) {
+            assert_eq!(automata.accepts(&word), original.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("p".into());
+        automata.push_state("q".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+
+        let mut other = Automata::new();
+        other.push_symbol("b");
+        other.push_state("x".into());
+        other.push_state("y".into());
+        other.push_accept_state(1);
+        other.push_transition_from(0, Transition::new("b", 1));
+        other.push_transition_from(1, Transition::epsilon(0));
+
+        let offset = automata.merge(&other);
+
+        assert_eq!(offset, 2);
+        assert_eq!(automata.len(), 4);
+        assert_eq!(*automata.alphabet, ["a", "b"]);
+        assert_eq!(&automata.accept_states, &[1, 3]);
+        assert_eq!(automata.find(&"x".into()), Some(2));
+        assert_eq!(automata.transitions_from(2), &[Transition::new("b", 3)]);
+        assert_eq!(automata.transitions_from(3), &[Transition::epsilon(2)]);
+        assert_eq!(automata.entry_state, 0);
+    }
+
+    #[test]
+    fn to_deterministic_stats() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let (afd_automata, stats) = automata.to_deterministic_stats();
+
+        assert_eq!(
+            format!("{}", afd_automata),
+            format!("{}", automata.to_deterministic())
+        );
+        assert_eq!(
+            stats,
+            DeterminizeStats {
+                states_created: 4,
+                membership_checks: 4,
+                max_frontier: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn accepts_detailed() {
+        let automata = FileParser::with_filename("tests/afn2").parse().unwrap();
+
+        assert_eq!(automata.accepts_detailed(&["0"]), Acceptance::Accepted);
+        assert_eq!(automata.accepts_detailed(&["0", "1"]), Acceptance::Rejected);
+        assert_eq!(
+            automata.accepts_detailed(&["0", "2", "1"]),
+            Acceptance::InvalidInput {
+                position: 1,
+                reason: "symbol \"2\" is not in the alphabet".to_owned()
+            }
+        );
+
+        match automata.accepts_detailed(&[""]) {
+            Acceptance::InvalidInput { position, .. } => assert_eq!(position, 0),
+            acceptance => panic!("Unexpected {:?}", acceptance),
+        }
+    }
+
+    #[test]
+    fn trim_transitions_to_dead() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let mut afd_automata = automata.to_deterministic();
+        let trap = afd_automata.find(&"!".into()).unwrap();
+        let len = afd_automata.len();
+
+        afd_automata.trim_transitions_to_dead();
+
+        assert_eq!(afd_automata.len(), len);
+        assert_eq!(afd_automata.find(&"!".into()), Some(trap));
+        for index in 0..afd_automata.len() {
+            for transition in afd_automata.transitions_from(index) {
+                assert_ne!(transition.end_state(), trap);
+            }
+        }
+
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(afd_automata.accepts(&word), automata.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn common_suffix() {
+        let mut automata = Automata::new();
+        automata.push_state("start".into());
+        for word in &["sing", "ring", "going"] {
+            let mut last = 0;
+            for (position, symbol) in word.chars().enumerate() {
+                let symbol = symbol.to_string();
+                if !automata.alphabet.contains(&symbol) {
+                    automata.push_symbol(&symbol);
+                }
+
+                automata.push_state(format!("{}{}", word, position).into());
+                let index = automata.len() - 1;
+                automata.push_transition_from(last, Transition::new(&symbol, index));
+                last = index;
+            }
+
+            automata.push_accept_state(last);
+        }
+
+        assert_eq!(automata.common_suffix(), "ing");
+
+        automata.push_accept_state(0);
+        assert_eq!(automata.common_suffix(), "");
+
+        let mut empty = Automata::new();
+        empty.push_symbol("a");
+        empty.push_state("0".into());
+        empty.push_transition_from(0, Transition::new("a", 0));
+        assert_eq!(empty.common_suffix(), "");
+    }
+
+    #[test]
+    fn classify_report() {
+        // (0|1)*011
+        let automata = FileParser::with_filename("tests/afn3").parse().unwrap();
+
+        assert_eq!(automata.tokenize("0110"), Some(vec!["0", "1", "1", "0"]));
+        assert_eq!(automata.tokenize("01a"), None);
+
+        let report =
+            automata.classify_report(&["011", "1011", "0110", "01x"], &["", "01", "00011"]);
+        assert!(!report.passed());
+        assert_eq!(report.false_accepts, vec!["00011".to_owned()]);
+        assert_eq!(
+            report.false_rejects,
+            vec!["0110".to_owned(), "01x".to_owned()]
+        );
+
+        assert!(automata.classify_report(&["011"], &["0"]).passed());
+    }
+
+    #[test]
+    fn suffix_language() {
+        let mut automata = Automata::new();
+        for symbol in &["a", "b", "c"] {
+            automata.push_symbol(symbol);
+        }
+
+        for (index, symbol) in ["a", "b", "c"].iter().enumerate() {
+            automata.push_state(index.to_string().into());
+            automata.push_transition_from(index, Transition::new(symbol, index + 1));
+        }
+
+        automata.push_state("3".into());
+        automata.push_accept_state(3);
+
+        let suffixes = automata.suffix_language();
+        assert!(suffixes.accepts(&["c"]));
+        assert!(suffixes.accepts(&["b", "c"]));
+        assert!(suffixes.accepts(&["a", "b", "c"]));
+        assert!(suffixes.accepts(&[]));
+        assert!(!suffixes.accepts(&["a", "b"]));
+        assert!(!suffixes.accepts(&["b"]));
+        assert!(!suffixes.accepts(&["a", "c"]));
+    }
+
+    #[test]
+    fn factor_language() {
+        let mut automata = Automata::new();
+        for symbol in &["a", "b", "c"] {
+            automata.push_symbol(symbol);
+        }
+
+        for (index, symbol) in ["a", "b", "c"].iter().enumerate() {
+            automata.push_state(index.to_string().into());
+            automata.push_transition_from(index, Transition::new(symbol, index + 1));
+        }
+
+        automata.push_state("3".into());
+        automata.push_state("dead".into());
+        automata.push_accept_state(3);
+        automata.push_transition_from(0, Transition::new("c", 4));
+
+        let factors = automata.factor_language();
+        assert!(factors.accepts(&[]));
+        assert!(factors.accepts(&["b"]));
+        assert!(factors.accepts(&["a", "b"]));
+        assert!(factors.accepts(&["b", "c"]));
+        assert!(factors.accepts(&["a", "b", "c"]));
+        assert!(!factors.accepts(&["a", "c"]));
+        assert!(!factors.accepts(&["c", "a"]));
+        assert!(!factors.accepts(&["c", "b"]));
+    }
+
+    #[test]
+    fn count_states_visited() {
+        let afn_automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = afn_automata.to_deterministic();
+
+        assert_eq!(afd_automata.count_states_visited(&[]), 1);
+        assert_eq!(afd_automata.count_states_visited(&["a"]), 2);
+        assert_eq!(afd_automata.count_states_visited(&["b", "b", "a", "b"]), 3);
+        assert_eq!(
+            afd_automata.count_states_visited(&["a", "a", "b", "a", "b"]),
+            4
+        );
+        assert_eq!(Automata::new().count_states_visited(&["a"]), 0);
+    }
+
+    #[test]
+    fn to_deterministic_accepting_entry() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::epsilon(1));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        automata.push_transition_from(2, Transition::new("a", 1));
+
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.accept_states.contains(&0));
+        assert!(afd_automata.accepts(&[]));
+        assert!(afd_automata.accepts(&["a", "a"]));
+        assert!(!afd_automata.accepts(&["a"]));
+
+        automata.push_accept_state(0);
+        let (afd_automata, _) = automata.to_deterministic_stats();
+        assert_eq!(&afd_automata.accept_states, &[0, 2]);
+        assert!(afd_automata.accepts(&[]));
+    }
+
+    #[test]
+    fn to_dot_with() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+
+        let options = DotOptions {
+            subset_labels: false,
+        };
+        let dot = afd_automata.to_dot_with(options);
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("    0 [label=\"0\", shape=circle];"));
+        assert!(dot.contains("    1 [label=\"1\", shape=doublecircle];"));
+        assert!(dot.contains("    start -> 0;"));
+        assert!(dot.contains("    0 -> 1 [label=\"a\"];"));
+
+        let options = DotOptions {
+            subset_labels: true,
+        };
+        let dot = afd_automata.to_dot_with(options);
+        assert_eq!(dot, afd_automata.to_dot());
+        assert!(dot.contains("    0 [label=\"0,2,3,4\", shape=circle];"));
+        assert!(dot.contains("    1 [label=\"1,3,4,5\", shape=doublecircle];"));
+        assert!(dot.contains("    4 [label=\"!\", shape=circle];"));
+
+        let parsed = Automata::from_dot(&dot).unwrap();
+        assert_eq!(parsed.states, afd_automata.states);
+        assert_eq!(parsed.accept_states, afd_automata.accept_states);
+        assert_eq!(parsed.transitions, afd_automata.transitions);
+    }
+
+    #[test]
+    fn minimize_partial() {
+        // ab*, with 1, 2 and 3 equivalent
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..5 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for &(from, symbol, to) in &[
+            (0, "a", 1),
+            (0, "b", 4),
+            (1, "a", 4),
+            (1, "b", 2),
+            (2, "a", 4),
+            (2, "b", 3),
+            (3, "a", 4),
+            (3, "b", 2),
+            (4, "a", 4),
+            (4, "b", 4),
+        ] {
+            automata.push_transition_from(from, Transition::new(symbol, to));
+        }
+
+        for index in 1..4 {
+            automata.push_accept_state(index);
+        }
+
+        let total = automata.minimize();
+        let partial = automata.minimize_partial();
+        assert_eq!(total.len(), 3);
+        assert_eq!(partial.len(), total.len() - 1);
+        assert_eq!(partial[1], State::from(&["1", "2", "3"][..]));
+        assert!(partial.find(&"4".into()).is_none());
+        for word in words(&["a", "b"], 5) {
+            assert_eq!(partial.accepts(&word), automata.accepts(&word));
+        }
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let partial = automata.minimize_partial();
+        assert_eq!(partial.len(), 4);
+        assert!(partial.find(&"!".into()).is_none());
+        for word in words(&["a", "b"], 5) {
+            assert_eq!(partial.accepts(&word), automata.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        assert_eq!(automata.validate(), Ok(()));
+
+        assert_eq!(
+            automata.insert_alphabet_symbol_at(0, "-1"),
+            Err(AutomataError::EpsilonSymbol("-1".to_owned()))
+        );
+
+        let mut broken = automata.clone();
+        broken.push_transition_from(1, Transition::new("a", 2));
+        assert_eq!(
+            broken.validate(),
+            Err(AutomataError::IndexOutOfBounds { index: 2, len: 2 })
+        );
+
+        automata.push_symbol("");
+        assert_eq!(
+            automata.validate(),
+            Err(AutomataError::EpsilonSymbol("".to_owned()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid alphabet: Symbol \"-1\" is reserved for epsilon")]
+    fn to_deterministic_epsilon_symbol() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("-1");
+        automata.push_state("0".into());
+        automata.push_transition_from(0, Transition::new("-1", 0));
+
+        assert_eq!(
+            automata.try_to_deterministic().unwrap_err(),
+            AutomataError::EpsilonSymbol("-1".to_owned())
+        );
+        assert_eq!(
+            automata.to_deterministic_strict().unwrap_err(),
+            AutomataError::EpsilonSymbol("-1".to_owned())
+        );
+        automata.to_deterministic();
+    }
+
+    #[test]
+    fn to_deterministic_with_subsets() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let (afd_automata, subsets) = automata.to_deterministic_with_subsets();
+
+        assert_eq!(
+            format!("{}", afd_automata),
+            format!("{}", automata.to_deterministic())
+        );
+        assert_eq!(
+            subsets,
+            vec![
+                vec![0, 2, 3, 4],
+                vec![1, 3, 4, 5],
+                vec![3, 4],
+                vec![5],
+                vec![]
+            ]
+        );
+
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("x".into());
+        automata.push_state("z".into());
+        automata.push_state("y".into());
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("a", 2));
+        automata.push_transition_from(1, Transition::epsilon(0));
+
+        let (afd_automata, subsets) = automata.to_deterministic_with_subsets();
+        assert_eq!(afd_automata[1], State::from(&["x", "y", "z"][..]));
+        assert_eq!(subsets, vec![vec![0], vec![0, 1, 2]]);
+
+        // two states share the tag "x"
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("x".into());
+        automata.push_state("x".into());
+        automata.push_state("y".into());
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("a", 2));
+        automata.push_transition_from(1, Transition::new("a", 2));
+
+        let (afd_automata, subsets) = automata.to_deterministic_with_subsets();
+        for word in words(&["a"], 4) {
+            assert_eq!(afd_automata.accepts(&word), automata.accepts(&word));
+        }
+
+        assert!(afd_automata.accepts(&["a", "a"]));
+        assert!(!afd_automata.accepts(&["a", "a", "a"]));
+        assert_eq!(subsets, vec![vec![0], vec![1, 2], vec![2], vec![]]);
+    }
+
+    #[test]
+    fn accepts_dead_state() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+
+        let mut input = vec!["b", "a", "a"];
+        input.extend(std::iter::repeat_n("a", 1000));
+        assert_eq!(
+            automata.accepts_consumed(&input, usize::MAX, &automata.dead_mask()),
+            Ok((false, 3))
+        );
+        assert_eq!(
+            afd_automata.accepts_consumed(&input, usize::MAX, &afd_automata.dead_mask()),
+            Ok((false, 3))
+        );
+        assert!(!afd_automata.accepts(&input));
+
+        assert_eq!(
+            automata.accepts_consumed(&["a", "a"], usize::MAX, &automata.dead_mask()),
+            Ok((true, 2))
+        );
+        assert_eq!(
+            afd_automata.accepts_consumed(&["b", "a"], usize::MAX, &afd_automata.dead_mask()),
+            Ok((true, 2))
+        );
+        assert_eq!(
+            automata.accepts_consumed(&[], usize::MAX, &automata.dead_mask()),
+            Ok((false, 0))
+        );
+    }
+
+    #[test]
+    fn to_deterministic_shared_targets() {
+        // every state moves to 3 and 4 on "a", so the moves overlap
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        for index in 0..5 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for index in 0..3 {
+            automata.push_transition_from(0, Transition::epsilon(index));
+            automata.push_transition_from(index, Transition::new("a", 3));
+            automata.push_transition_from(index, Transition::new("a", 4));
+        }
+
+        automata.push_transition_from(4, Transition::epsilon(3));
+        automata.push_accept_state(3);
+
+        let (afd_automata, _) = automata.to_deterministic_stats();
+        assert_eq!(
+            format!("{}", afd_automata),
+            format!("{}", automata.to_deterministic())
+        );
+        assert_eq!(afd_automata.len(), 3);
+        assert_eq!(afd_automata[1], State::from(&["3", "4"][..]));
+        assert!(afd_automata.accepts(&["a"]));
+        assert!(!afd_automata.accepts(&["a", "a"]));
+    }
+
+    #[test]
+    fn language_intersection_witness() {
+        // (0|1)*011
+        let automata = FileParser::with_filename("tests/afn3").parse().unwrap();
+
+        let mut odd_ones = Automata::new();
+        odd_ones.push_symbol("0");
+        odd_ones.push_symbol("1");
+        odd_ones.push_state("even".into());
+        odd_ones.push_state("odd".into());
+        odd_ones.push_accept_state(1);
+        for index in 0..2 {
+            odd_ones.push_transition_from(index, Transition::new("0", index));
+            odd_ones.push_transition_from(index, Transition::new("1", 1 - index));
+        }
+
+        let witness = automata.language_intersection_witness(&odd_ones).unwrap();
+        assert_eq!(witness, "1011");
+        let word: Vec<_> = witness.split("").filter(|s| !s.is_empty()).collect();
+        assert!(automata.accepts(&word));
+        assert!(odd_ones.accepts(&word));
+
+        let mut only_zeros = Automata::new();
+        only_zeros.push_symbol("0");
+        only_zeros.push_state("0".into());
+        only_zeros.push_accept_state(0);
+        only_zeros.push_transition_from(0, Transition::new("0", 0));
+        assert_eq!(automata.language_intersection_witness(&only_zeros), None);
+        assert_eq!(
+            only_zeros.language_intersection_witness(&only_zeros),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn relabel_with() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let mut afd_automata = automata.to_deterministic();
+
+        assert_eq!(
+            afd_automata.relabel_with(&["s0", "s1"]),
+            Err(AutomataError::LengthMismatch {
+                expected: 5,
+                found: 2
+            })
+        );
+        assert_eq!(
+            afd_automata.relabel_with(&["s0", "s1", "s2", "s1", "trap"]),
+            Err(AutomataError::DuplicateTag("s1".to_owned()))
+        );
+        assert_eq!(afd_automata[0], State::from(&["0", "2", "3", "4"][..]));
+
+        let names = ["s0", "s1", "s2", "s3", "trap"];
+        assert_eq!(afd_automata.relabel_with(&names), Ok(()));
+        for (index, name) in names.iter().enumerate() {
+            assert_eq!(afd_automata[index], State::from(*name));
+        }
+        assert_eq!(afd_automata.find(&"trap".into()), Some(4));
+        assert!(afd_automata.accepts(&["a", "a"]));
+    }
+
+    #[test]
+    fn transition_matrix_ordered() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let mut afd_automata = automata.to_deterministic_with(TrapPolicy::None);
+
+        assert_eq!(
+            afd_automata.transition_matrix_ordered(&["a", "b"]),
+            Ok(vec![
+                vec![Some(1), Some(2)],
+                vec![Some(3), None],
+                vec![Some(3), None],
+                vec![None, None],
+            ])
+        );
+        assert_eq!(
+            afd_automata.transition_matrix_ordered(&["b", "a"]),
+            Ok(vec![
+                vec![Some(2), Some(1)],
+                vec![None, Some(3)],
+                vec![None, Some(3)],
+                vec![None, None],
+            ])
+        );
+        assert_eq!(
+            afd_automata.transition_matrix_ordered(&["a"]),
+            Err(AutomataError::MissingSymbol("b".to_owned()))
+        );
+
+        afd_automata.set_default_transition(3, 0);
+        let matrix = afd_automata.transition_matrix_ordered(&["c", "b", "a"]);
+        assert_eq!(matrix.unwrap()[3], vec![Some(0), Some(0), Some(0)]);
+    }
+
+    #[test]
+    fn is_reversible() {
+        // a cycle of three states, counting the a's modulo 3
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..3 {
+            automata.push_state(index.to_string().into());
+            automata.push_transition_from(index, Transition::new("a", (index + 1) % 3));
+            automata.push_transition_from(index, Transition::new("b", index));
+        }
+
+        automata.push_accept_state(0);
+        assert!(automata.is_reversible());
+
+        let afd_automata = FileParser::with_filename("tests/afn1")
+            .parse()
+            .unwrap()
+            .to_deterministic();
+        assert!(!afd_automata.is_reversible());
+
+        // 1 has edges on every symbol, its default is never taken
+        automata.set_default_transition(1, 0);
+        assert!(automata.is_reversible());
+
+        // 3 enters 0 on b through its default, as 0 does explicitly
+        automata.push_state("3".into());
+        automata.push_transition_from(3, Transition::new("a", 3));
+        automata.set_default_transition(3, 0);
+        assert!(!automata.is_reversible());
+    }
+
+    #[test]
+    fn concat_all() {
+        let parts: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|symbol| {
+                let mut automata = Automata::new();
+                automata.push_symbol(symbol);
+                automata.push_state("q0".into());
+                automata.push_state("q1".into());
+                automata.push_accept_state(1);
+                automata.push_transition_from(0, Transition::new(symbol, 1));
+                automata
+            })
+            .collect();
+
+        let automata = Automata::concat_all(&parts);
+        assert_eq!(*automata.alphabet, ["a", "b", "c"]);
+        assert_eq!(automata.len(), 6);
+        assert_eq!(automata.find(&"2.q1".into()), Some(5));
+        assert_eq!(&automata.accept_states, &[5]);
+        for word in words(&["a", "b", "c"], 4) {
+            assert_eq!(automata.accepts(&word), word == ["a", "b", "c"]);
+        }
+
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.accepts(&["a", "b", "c"]));
+        assert!(!afd_automata.accepts(&["a", "b"]));
+
+        let single = Automata::concat_all(&parts[1..2]);
+        assert_eq!(format!("{}", single), format!("{}", parts[1]));
+
+        let empty = Automata::concat_all(&[]);
+        assert!(empty.accepts(&[]));
+        assert!(!empty.accepts(&["a"]));
+    }
+
+    #[test]
+    fn minimize() {
+        // words containing aa, 3 behaves as 0 and 4 as 2
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..5 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for &(from, symbol, to) in &[
+            (0, "a", 1),
+            (0, "b", 3),
+            (1, "a", 2),
+            (1, "b", 0),
+            (2, "a", 4),
+            (2, "b", 4),
+            (3, "a", 1),
+            (3, "b", 0),
+            (4, "a", 2),
+            (4, "b", 4),
+        ] {
+            automata.push_transition_from(from, Transition::new(symbol, to));
+        }
+
+        automata.push_accept_state(2);
+        automata.push_accept_state(4);
+
+        let minimal = automata.minimize();
+        assert_eq!(minimal.len(), 3);
+        assert!(minimal.is_deterministic());
+        assert_eq!(minimal[0], State::from(&["0", "3"][..]));
+        assert_eq!(minimal[1], State::from("1"));
+        assert_eq!(minimal[2], State::from(&["2", "4"][..]));
+        assert_eq!(&minimal.accept_states, &[2]);
+        for word in words(&["a", "b"], 6) {
+            assert_eq!(minimal.accepts(&word), automata.accepts(&word));
+        }
+
+        // the trap state is kept only while something leads to it
+        let afn_automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let minimal = afn_automata.minimize();
+        assert_eq!(minimal.len(), 5);
+        assert_eq!(minimal.find(&"!".into()), Some(4));
+        assert_eq!(minimal.minimize().len(), 5);
+        assert!(automata.minimize().find(&"!".into()).is_none());
+    }
+
+    #[test]
+    fn alternation() {
+        let parts: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|symbol| {
+                let mut automata = Automata::new();
+                automata.push_symbol(symbol);
+                automata.push_state("q0".into());
+                automata.push_state("q1".into());
+                automata.push_accept_state(1);
+                automata.push_transition_from(0, Transition::new(symbol, 1));
+                automata
+            })
+            .collect();
+
+        let automata = Automata::alternation(&parts);
+        assert_eq!(automata.len(), 7);
+        assert_eq!(&automata.accept_states, &[2, 4, 6]);
+        for word in words(&["a", "b", "c"], 3) {
+            assert_eq!(automata.accepts(&word), word.len() == 1);
+        }
+
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.accepts(&["c"]));
+        assert!(!afd_automata.accepts(&["a", "b"]));
+
+        let empty = Automata::alternation(&[]);
+        assert_eq!(empty.len(), 1);
+        assert!(!empty.accepts(&[]));
+    }
+
+    #[test]
+    fn accepts() {
+        // (0|1)*011, with epsilon transitions
+        let automata = FileParser::with_filename("tests/afn3").parse().unwrap();
+        assert!(automata.accepts(&["0", "1", "1"]));
+        assert!(automata.accepts(&["1", "1", "0", "0", "1", "1"]));
+        assert!(!automata.accepts(&[]));
+        assert!(!automata.accepts(&["0", "1", "1", "0"]));
+        assert!(!automata.accepts(&["0", "2", "1", "1"]));
+
+        // partial DFA: b has no transition from the entry
+        let mut afd_automata = Automata::new();
+        afd_automata.push_symbol("a");
+        afd_automata.push_symbol("b");
+        afd_automata.push_state("0".into());
+        afd_automata.push_state("1".into());
+        afd_automata.push_accept_state(1);
+        afd_automata.push_transition_from(0, Transition::new("a", 1));
+        afd_automata.push_transition_from(1, Transition::new("b", 1));
+        assert!(afd_automata.accepts(&["a"]));
+        assert!(afd_automata.accepts(&["a", "b", "b"]));
+        assert!(!afd_automata.accepts(&["b"]));
+        assert!(!afd_automata.accepts(&["a", "a"]));
+
+        let complete = afd_automata.to_deterministic();
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(complete.accepts(&word), afd_automata.accepts(&word));
+            assert_eq!(complete.accepts(&word), walk(&complete, &word));
+        }
+    }
+
+    #[test]
+    fn to_deterministic_rediscovered_accept() {
+        // {1, 2} is reached from the entry on a and from {3} on b
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..4 {
+            automata.push_state(index.to_string().into());
+        }
+
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("b", 3));
+        automata.push_transition_from(1, Transition::epsilon(2));
+        automata.push_transition_from(3, Transition::new("b", 1));
+        automata.push_transition_from(3, Transition::new("b", 2));
+
+        let (afd_automata, _) = automata.to_deterministic_stats();
+        let accepting = afd_automata.find(&(&["1", "2"][..]).into()).unwrap();
+        assert_eq!(afd_automata.move_from_with(0, "a"), vec![accepting]);
+        let three = afd_automata.move_from_with(0, "b")[0];
+        assert_eq!(afd_automata.move_from_with(three, "b"), vec![accepting]);
+        assert_eq!(&afd_automata.accept_states, &[accepting]);
+        assert!(afd_automata.accepts(&["a"]));
+        assert!(afd_automata.accepts(&["b", "b"]));
+        assert!(!afd_automata.accepts(&["b"]));
+    }
+
+    #[test]
+    fn to_dot() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+
+        let dot = automata.to_dot();
+        assert!(dot.contains("    start [shape=point, style=invis];"));
+        assert!(dot.contains("    start -> 0;"));
+        assert!(dot.contains("    5 [label=\"5\", shape=doublecircle];"));
+        assert!(dot.contains("    0 -> 2 [label=\"ε\"];"));
+        assert!(dot.ends_with("}\n"));
+
+        let afd_automata = automata.to_deterministic();
+        let dot = afd_automata.to_dot();
+        assert!(dot.contains("    3 -> 4 [label=\"a,b\"];"));
+        assert!(dot.contains("    4 -> 4 [label=\"a,b\"];"));
+        assert!(!dot.contains("    3 -> 4 [label=\"a\"];"));
+
+        let parsed = Automata::from_dot(&automata.to_dot()).unwrap();
+        assert_eq!(parsed.transition_relation(), automata.transition_relation());
+        assert_eq!(parsed.accept_states, automata.accept_states);
+        let parsed = Automata::from_dot(&dot).unwrap();
+        assert_eq!(
+            parsed.transition_relation(),
+            afd_automata.transition_relation()
+        );
+    }
+
+    #[test]
+    fn transitions_summary() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("q0".into());
+        automata.push_state((&["q1", "q2"][..]).into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("b", 0));
+        automata.push_transition_from(1, Transition::epsilon(0));
+
+        assert_eq!(
+            automata.transitions_summary(),
+            ">q0 -a-> q1,q2, -b-> q0\n*q1,q2 -ε-> q0\n"
+        );
+    }
+
+    #[test]
+    fn to_deterministic_with_provenance() {
+        let mut automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        automata
+            .relabel_with(&["q0", "q1", "q2", "q3", "q4", "q5"])
+            .unwrap();
+
+        let afd_automata = automata.to_deterministic_with_provenance();
+        for index in 0..afd_automata.len() {
+            assert_eq!(afd_automata[index], State::from(index.to_string()));
+        }
+
+        let provenance = |tags: &[&str]| tags.iter().map(|t| (*t).to_owned()).collect();
+        assert_eq!(
+            afd_automata.provenance(0),
+            Some(&provenance(&["q0", "q2", "q3", "q4"]))
+        );
+        assert_eq!(afd_automata.provenance(3), Some(&provenance(&["q5"])));
+        assert_eq!(afd_automata.provenance(4), Some(&provenance(&["!"])));
+        assert_eq!(afd_automata.provenance(5), None);
+        assert_eq!(automata.provenance(0), None);
+        assert_eq!(&afd_automata.accept_states, &[1, 3]);
+        assert!(afd_automata.accepts(&["a", "a"]));
+    }
+
+    #[test]
+    fn union() {
+        let star = |symbol: &str| {
+            let mut automata = Automata::new();
+            automata.push_symbol(symbol);
+            automata.push_state("q0".into());
+            automata.push_accept_state(0);
+            automata.push_transition_from(0, Transition::new(symbol, 0));
+            automata
+        };
+
+        let automata = star("a").union(&star("b"));
+        assert_eq!(*automata.alphabet, ["a", "b"]);
+        assert_eq!(automata.find(&"1.q0".into()), Some(2));
+        for automata in &[automata.clone(), automata.to_deterministic()] {
+            assert!(automata.accepts(&[]));
+            assert!(automata.accepts(&["a", "a", "a"]));
+            assert!(automata.accepts(&["b", "b"]));
+            assert!(!automata.accepts(&["a", "b"]));
+        }
+    }
+
+    #[test]
+    fn is_minimal() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert!(!automata.is_minimal());
+
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.is_minimal());
+        assert!(!automata
+            .to_deterministic_with(TrapPolicy::None)
+            .is_minimal());
+
+        // words containing aa, 3 behaves as 0 and 4 as 2
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..5 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for &(from, a, b) in &[(0, 1, 3), (1, 2, 0), (2, 4, 4), (3, 1, 0), (4, 2, 4)] {
+            automata.push_transition_from(from, Transition::new("a", a));
+            automata.push_transition_from(from, Transition::new("b", b));
+        }
+
+        automata.push_accept_state(2);
+        automata.push_accept_state(4);
+        assert!(automata.is_deterministic());
+        assert_eq!(automata.reachable_states().len(), 5);
+        assert!(!automata.is_minimal());
+        assert!(automata.minimize().is_minimal());
+    }
+
+    #[test]
+    fn intersection() {
+        let mut even_a = Automata::new();
+        even_a.push_symbol("a");
+        even_a.push_symbol("b");
+        even_a.push_state("even".into());
+        even_a.push_state("odd".into());
+        even_a.push_accept_state(0);
+        for index in 0..2 {
+            even_a.push_transition_from(index, Transition::new("a", 1 - index));
+            even_a.push_transition_from(index, Transition::new("b", index));
+        }
+
+        let mut some_b = Automata::new();
+        some_b.push_symbol("a");
+        some_b.push_symbol("b");
+        some_b.push_state("none".into());
+        some_b.push_state("seen".into());
+        some_b.push_accept_state(1);
+        some_b.push_transition_from(0, Transition::new("a", 0));
+        some_b.push_transition_from(0, Transition::new("b", 1));
+        some_b.push_transition_from(1, Transition::new("a", 1));
+        some_b.push_transition_from(1, Transition::new("b", 1));
+
+        let automata = even_a.intersection(&some_b);
+        assert_eq!(automata.len(), 4);
+        assert!(automata.is_deterministic());
+        assert_eq!(automata[0], State::from("(even,none)"));
+        assert!(automata.find(&"(even,seen)".into()).is_some());
+        for word in words(&["a", "b"], 5) {
+            let expected =
+                word.iter().filter(|s| **s == "a").count() % 2 == 0 && word.contains(&"b");
+            assert_eq!(automata.accepts(&word), expected);
+        }
+
+        assert!(format!("{}", automata).contains("0 = { (even,none) }"));
+    }
+
+    #[test]
+    fn path_weight() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::weighted("a", 1, 0.5));
+        automata.push_transition_from(1, Transition::weighted("a", 1, 0.25));
+        automata.push_transition_from(1, Transition::new("b", 0));
+
+        assert_eq!(automata.path_weight(&["a"]), Some(0.5));
+        assert_eq!(
+            automata.path_weight(&["a", "a", "a"]),
+            Some(0.5 * 0.25 * 0.25)
+        );
+        assert_eq!(automata.path_weight(&["a", "b", "a"]), Some(0.25));
+        assert_eq!(automata.path_weight(&["a", "b"]), None);
+        assert_eq!(automata.path_weight(&["b"]), None);
+
+        assert_ne!(Transition::weighted("a", 1, 0.5), Transition::new("a", 1));
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.accepts(&["a", "b", "a"]));
+        assert_eq!(afd_automata.path_weight(&["a", "a"]), Some(1.0));
+    }
+
+    #[test]
+    fn complement() {
+        // words ending in ab, as a partial NFA
+        let mut automata = Automata::new();
+        for symbol in &["a", "b", "x"] {
+            automata.push_symbol(symbol);
+        }
+
+        for index in 0..3 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for symbol in &["a", "b", "x"] {
+            automata.push_transition_from(0, Transition::new(symbol, 0));
+        }
+
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 2));
+        automata.push_accept_state(2);
+
+        let complement = automata.complement();
+        assert!(complement.is_deterministic());
+        assert!(!complement.accepts(&["x", "a", "b"]));
+        assert!(complement.accepts(&["a", "b", "x"]));
+        assert!(complement.accepts(&[]));
+
+        let double = complement.complement();
+        for word in words(&["a", "b", "x"], 4) {
+            let ends_in_ab = word.ends_with(&["a", "b"]);
+            assert_eq!(complement.accepts(&word), !ends_in_ab);
+            assert_eq!(double.accepts(&word), ends_in_ab);
+        }
+
+        assert_eq!(double.minimize().len(), automata.minimize().len());
+
+        let mut empty = Automata::new();
+        for symbol in &["a", "b", "x"] {
+            empty.push_symbol(symbol);
+        }
+
+        let complement = empty.complement();
+        assert!(complement.is_deterministic());
+        let double = complement.complement();
+        for word in words(&["a", "b", "x"], 3) {
+            assert!(complement.accepts(&word));
+            assert!(!double.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn to_deterministic_collapsing_universal() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..3 {
+            automata.push_state(index.to_string().into());
+            automata.push_accept_state(index);
+        }
+
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(0, Transition::new("b", 2));
+        automata.push_transition_from(1, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 2));
+        automata.push_transition_from(2, Transition::new("a", 0));
+        automata.push_transition_from(2, Transition::new("b", 1));
+        automata.push_transition_from(2, Transition::epsilon(1));
+
+        assert_eq!(automata.to_deterministic().len(), 4);
+        let universal = automata.to_deterministic_collapsing_universal();
+        assert_eq!(universal.len(), 1);
+        assert_eq!(universal[0], State::from(&["0", "1", "2"][..]));
+        assert_eq!(&universal.accept_states, &[0]);
+        assert_eq!(
+            universal.transitions_from(0),
+            &[Transition::new("a", 0), Transition::new("b", 0)]
+        );
+
+        // every NFA state accepts, but some words reach the trap state
+        automata.transitions[1].clear();
+        let afd_automata = automata.to_deterministic_collapsing_universal();
+        assert!(afd_automata.find(&"!".into()).is_some());
+        assert!(!afd_automata.accepts(&["a", "a"]));
+    }
+
+    #[test]
+    fn concat() {
+        let single = |symbol: &str| {
+            let mut automata = Automata::new();
+            automata.push_symbol(symbol);
+            automata.push_state("q0".into());
+            automata.push_state("q1".into());
+            automata.push_accept_state(1);
+            automata.push_transition_from(0, Transition::new(symbol, 1));
+            automata
+        };
+
+        let automata = single("a").concat(&single("b"));
+        assert_eq!(*automata.alphabet, ["a", "b"]);
+        assert_eq!(&automata.accept_states, &[3]);
+        assert_eq!(automata.transitions_from(1), &[Transition::epsilon(2)]);
+        for automata in &[automata.clone(), automata.to_deterministic()] {
+            for word in words(&["a", "b"], 3) {
+                assert_eq!(automata.accepts(&word), word == ["a", "b"]);
+            }
+        }
+    }
+
+    #[test]
+    fn kleene_star() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..3 {
+            automata.push_state(index.to_string().into());
+        }
+
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 2));
+
+        let star = automata.kleene_star();
+        assert_eq!(star.len(), 4);
+        assert_eq!(star.find(&"^".into()), Some(3));
+        assert_eq!(&star.accept_states, &[3]);
+        for automata in &[star.clone(), star.to_deterministic(), star.kleene_star()] {
+            assert!(automata.accepts(&[]));
+            assert!(automata.accepts(&["a", "b"]));
+            assert!(automata.accepts(&["a", "b", "a", "b"]));
+            assert!(!automata.accepts(&["a", "b", "a"]));
+            assert!(!automata.accepts(&["b", "a"]));
+        }
+    }
+
+    #[test]
+    fn to_deterministic_strict() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic_strict().unwrap();
+        assert_eq!(
+            format!("{}", afd_automata),
+            format!("{}", automata.to_deterministic())
+        );
+
+        // the transitions leave from 1, but nothing leads there from the entry
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(1, Transition::new("a", 1));
+
+        assert_eq!(automata.to_deterministic().len(), 1);
+        assert_eq!(
+            automata.to_deterministic_strict().unwrap_err(),
+            AutomataError::EmptyLanguage
+        );
+    }
+
+    #[test]
+    fn reverse() {
+        let mut automata = Automata::new();
+        for symbol in &["a", "b", "c"] {
+            automata.push_symbol(symbol);
+        }
+
+        for (index, symbol) in ["a", "b", "c"].iter().enumerate() {
+            automata.push_state(index.to_string().into());
+            automata.push_transition_from(index, Transition::new(symbol, index + 1));
+        }
+
+        automata.push_state("3".into());
+        automata.push_accept_state(3);
+
+        let reversed = automata.reverse();
+        assert_eq!(reversed.len(), 5);
+        assert_eq!(&reversed.accept_states, &[0]);
+        assert_eq!(reversed.transitions_from(4), &[Transition::epsilon(3)]);
+        for automata in &[reversed.clone(), reversed.to_deterministic()] {
+            assert!(automata.accepts(&["c", "b", "a"]));
+            assert!(!automata.accepts(&["a", "b", "c"]));
+            assert!(!automata.accepts(&["c", "b"]));
+        }
+
+        let twice = reversed.reverse();
+        for word in words(&["a", "b", "c"], 4) {
+            assert_eq!(twice.accepts(&word), automata.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn to_deterministic_moderately_large() {
+        // the sixth symbol from the end is an a
+        let automata = crate::regex::from_regex("(a|b)*a(a|b)(a|b)(a|b)(a|b)(a|b)").unwrap();
+        assert_eq!(automata.len(), 40);
+
+        let (afd_automata, stats) = automata.to_deterministic_stats();
+        assert_eq!(
+            format!("{}", afd_automata),
+            format!("{}", automata.to_deterministic())
+        );
+        assert_eq!(stats.states_created, 65);
+        assert_eq!(afd_automata.minimize().len(), 64);
+        for word in words(&["a", "b"], 8) {
+            let expected = word.len() >= 6 && word[word.len() - 6] == "a";
+            assert_eq!(afd_automata.accepts(&word), expected);
+        }
+    }
+
+    #[test]
+    fn remove_unreachable() {
+        let mut automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        automata.push_state("island".into());
+        automata.push_state("other".into());
+        automata.push_accept_state(6);
+        automata.push_transition_from(6, Transition::new("a", 5));
+        automata.push_transition_from(7, Transition::epsilon(6));
+        assert_eq!(automata.len(), 8);
+
+        let trimmed = automata.remove_unreachable();
+        assert_eq!(trimmed.len(), 6);
+        assert!(trimmed.find(&"island".into()).is_none());
+        assert_eq!(&trimmed.accept_states, &[5]);
+        assert_eq!(trimmed.transition_relation().len(), 7);
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(trimmed.accepts(&word), automata.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn symbol_transition_closure() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+
+        assert_eq!(automata.symbol_transition_closure(0, "a"), vec![1, 3, 4, 5]);
+        assert_eq!(automata.symbol_transition_closure(0, "b"), vec![3, 4]);
+        assert_eq!(automata.symbol_transition_closure(1, "a"), vec![5]);
+        assert!(automata.symbol_transition_closure(1, "b").is_empty());
+        assert!(automata.symbol_transition_closure(5, "a").is_empty());
+    }
+
+    #[test]
+    fn remove_dead() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+
+        let productive = afd_automata.remove_dead();
+        assert_eq!(productive.len(), 4);
+        assert!(productive.find(&"!".into()).is_none());
+        assert!(productive.is_trim());
+
+        let mut automata = automata.clone();
+        automata.push_state("island".into());
+        automata.push_state("sink".into());
+        automata.push_transition_from(0, Transition::new("b", 7));
+        automata.push_transition_from(6, Transition::new("a", 0));
+        assert!(!automata.is_trim());
+
+        let trimmed = automata.trim();
+        assert!(trimmed.is_trim());
+        assert_eq!(trimmed.len(), 6);
+        assert!(trimmed.find(&"sink".into()).is_none());
+        assert!(trimmed.find(&"island".into()).is_none());
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(trimmed.accepts(&word), automata.accepts(&word));
+            assert_eq!(productive.accepts(&word), automata.accepts(&word));
+        }
+    }
+
+    #[test]
+    fn to_deterministic_reproducible() {
+        let automata = FileParser::with_filename("tests/afn3").parse().unwrap();
+
+        let (first, _) = automata.to_deterministic_stats();
+        let expected = format!("{}", first);
+        for _ in 0..10 {
+            let (afd_automata, _) = automata.clone().to_deterministic_stats();
+            assert_eq!(format!("{}", afd_automata), expected);
+            assert_eq!(format!("{}", automata.to_deterministic()), expected);
+        }
+    }
+
+    #[test]
+    fn equivalent() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+        assert!(automata.equivalent(&afd_automata));
+        assert!(afd_automata.equivalent(&automata.minimize_partial()));
+        assert!(automata.equivalent(&automata.reverse().reverse()));
+
+        // (0|1)*011
+        let other = FileParser::with_filename("tests/afn3").parse().unwrap();
+        let regex = crate::regex::from_regex("(0|1)*011").unwrap();
+        assert!(other.equivalent(&regex));
+        assert!(!other.equivalent(&crate::regex::from_regex("(0|1)*11").unwrap()));
+        assert!(!automata.equivalent(&other));
+        assert!(!automata.equivalent(&automata.complement()));
+
+        let mut empty = Automata::new();
+        empty.push_symbol("a");
+        empty.push_state("0".into());
+        assert!(empty.equivalent(&Automata::new()));
+        assert!(!empty.equivalent(&automata));
+    }
+
+    #[test]
+    fn strip_to_language() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let sample = ["a", "ab", "bba"];
+        let stripped = automata.strip_to_language(&sample).unwrap();
+        assert!(stripped.is_minimal());
+        assert_eq!(stripped.alphabet, automata.alphabet);
+        for word in words(&["a", "b"], 4) {
+            let expected = sample.contains(&word.concat().as_str());
+            assert_eq!(stripped.accepts(&word), expected, "{:?}", word);
+        }
+
+        let stripped = automata.strip_to_language(&[]).unwrap();
+        assert!(stripped.is_empty_language());
+        assert!(!stripped.accepts(&[]));
+
+        assert_eq!(
+            automata.strip_to_language(&["a", "abc", "b"]).unwrap_err(),
+            AutomataError::MissingSymbol("abc".to_owned())
+        );
+    }
+
+    #[test]
+    fn is_deterministic_partial() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+
+        assert!(automata.is_deterministic());
+        assert!(!automata.is_complete());
+
+        let afd_automata = automata.to_deterministic();
+        assert!(afd_automata.is_deterministic());
+        assert!(afd_automata.is_complete());
+        assert_eq!(afd_automata.len(), 3);
+
+        let partial = automata.to_deterministic_with(TrapPolicy::None);
+        assert!(partial.is_deterministic());
+        assert!(!partial.is_complete());
+        assert_eq!(partial.len(), 2);
+
+        automata.push_transition_from(1, Transition::epsilon(0));
+        assert!(!automata.is_deterministic());
+        automata.transitions[1].clear();
+        automata.push_transition_from(0, Transition::new("a", 0));
+        assert!(!automata.is_deterministic());
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert!(!automata.is_deterministic());
+        assert!(automata.minimize_partial().is_deterministic());
+        assert!(!automata.minimize_partial().is_complete());
+    }
+
+    #[test]
+    fn to_deterministic_empty_language() {
+        // a chain of n states that can't reach the accept state, the full
+        // construction would create every one of them and a trap
+        let n = 20;
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..=n {
+            automata.push_state(index.to_string().into());
+        }
+
+        for index in 0..n - 1 {
+            automata.push_transition_from(index, Transition::new("a", index + 1));
+            automata.push_transition_from(index, Transition::epsilon(index + 1));
+        }
+
+        automata.push_accept_state(n);
+        automata.push_transition_from(n, Transition::new("b", 0));
+
+        let (afd_automata, complete) = automata.to_deterministic_budgeted(1);
+        assert!(complete);
+        assert_eq!(afd_automata.len(), 1);
+        assert!(afd_automata.accept_states.is_empty());
+        assert!(afd_automata.is_deterministic());
+        assert!(afd_automata.is_complete());
+        assert_eq!(afd_automata.move_from_with(0, "a"), vec![0]);
+        assert_eq!(afd_automata.move_from_with(0, "b"), vec![0]);
+        assert_eq!(afd_automata[0].len(), n);
+
+        let partial = automata.to_deterministic_with(TrapPolicy::None);
+        assert_eq!(partial.len(), 1);
+        assert!(partial.transitions_from(0).is_empty());
+
+        let (full, _) = automata.to_deterministic_stats();
+        assert!(full.len() > 1);
+        assert!(full.equivalent(&afd_automata));
+    }
+
+    #[test]
+    fn is_nfa() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_transition_from(0, Transition::new("a", 1));
+        assert!(!automata.has_epsilon());
+        assert!(!automata.is_nfa());
+
+        automata.push_transition_from(0, Transition::new("a", 0));
+        assert!(!automata.has_epsilon());
+        assert!(automata.is_nfa());
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert!(automata.is_nfa());
+        assert!(!automata.to_deterministic().is_nfa());
+        assert!(!automata.to_deterministic().has_epsilon());
+    }
+
+    #[test]
+    fn to_deterministic_with_priorities() {
+        // rule 1 is the keyword "if", rule 2 any word of "i" and "f"
+        let mut automata = Automata::new();
+        automata.push_symbol("i");
+        automata.push_symbol("f");
+        for state in &["start", "if", "word", "i", "w"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_transition_from(0, Transition::epsilon(3));
+        automata.push_transition_from(0, Transition::epsilon(4));
+        automata.push_transition_from(3, Transition::new("i", 3));
+        automata.push_transition_from(3, Transition::new("f", 1));
+        automata.push_transition_from(4, Transition::new("i", 2));
+        automata.push_transition_from(4, Transition::new("f", 2));
+        automata.push_transition_from(2, Transition::new("i", 2));
+        automata.push_transition_from(2, Transition::new("f", 2));
+        automata.push_accept_state(2);
+        automata.push_accept_state(1);
+
+        // the NFA state 3 loops on "i", so "iif" is a keyword too
+        let afd_automata = automata.to_deterministic_with_priorities();
+        let state_after = |word: &[&str]| {
+            word.iter().fold(afd_automata.entry_state, |index, symbol| {
+                afd_automata.move_from_with(index, symbol)[0]
+            })
+        };
+
+        assert_eq!(afd_automata.accept_priority(state_after(&[])), None);
+        assert_eq!(afd_automata.accept_priority(state_after(&["i"])), Some(2));
+        assert_eq!(
+            afd_automata.accept_priority(state_after(&["i", "f"])),
+            Some(1)
+        );
+        assert_eq!(
+            afd_automata.accept_priority(state_after(&["i", "i", "f"])),
+            Some(1)
+        );
+        assert_eq!(
+            afd_automata.accept_priority(state_after(&["f", "f"])),
+            Some(2)
+        );
+        assert_eq!(afd_automata.accept_priority(afd_automata.len()), None);
+        assert_eq!(automata.accept_priority(1), None);
+
+        for index in 0..afd_automata.len() {
+            let accepting = afd_automata.accept_states.contains(&index);
+            assert_eq!(afd_automata.accept_priority(index).is_some(), accepting);
+        }
+    }
+
+    #[test]
+    fn accepts_nfa_bounded() {
+        // after the first "a" every one of the n branches is active
+        let n = 10;
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        for index in 1..=n {
+            automata.push_state(index.to_string().into());
+            automata.push_transition_from(0, Transition::new("a", index));
+            automata.push_transition_from(index, Transition::new("a", index));
+        }
+
+        automata.push_accept_state(n);
+
+        assert_eq!(automata.accepts_nfa_bounded(&[], 1), Ok(false));
+        assert_eq!(
+            automata.accepts_nfa_bounded(&["a", "a"], 4),
+            Err(AutomataError::StateSetTooLarge { size: n, limit: 4 })
+        );
+        assert_eq!(automata.accepts_nfa_bounded(&["a", "a"], n), Ok(true));
+        assert!(automata.accepts(&["a", "a"]));
+
+        let afd_automata = automata.to_deterministic();
+        assert_eq!(afd_automata.accepts_nfa_bounded(&["a", "a"], 1), Ok(true));
+    }
+
+    #[test]
+    fn to_deterministic_trap_loops() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic_with(TrapPolicy::Named("reject".into()));
+        let trap = afd_automata.find(&"reject".into()).unwrap();
+        assert!(afd_automata.is_complete());
+        assert!(!afd_automata.accept_states.contains(&trap));
+        for symbol in afd_automata.alphabet.iter() {
+            assert_eq!(afd_automata.move_from_with(trap, symbol), vec![trap]);
+        }
+
+        // already complete, no trap needed
+        let minimal = afd_automata.minimize();
+        assert_eq!(minimal.to_deterministic().len(), minimal.len());
+        assert!(minimal.to_deterministic().find(&"!".into()).is_none());
+
+        let afd_automata = automata.to_deterministic();
+        let trap = afd_automata.find(&"!".into()).unwrap();
+        let trap_transitions = afd_automata.transitions_from(trap);
+        assert_eq!(trap_transitions.len(), afd_automata.alphabet.len());
+        assert!(trap_transitions.iter().all(|t| t.end_state() == trap));
+        assert!(!afd_automata.accepts(&["a", "a", "a", "b", "a"]));
+    }
+
+    #[test]
+    fn words_up_to() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let expected: Vec<Vec<String>> = [&["a"][..], &["a", "a"], &["b", "a"]]
+            .iter()
+            .map(|word| word.iter().map(|s| (*s).to_owned()).collect())
+            .collect();
+
+        assert_eq!(automata.words_up_to(3), expected);
+        assert_eq!(automata.to_deterministic().words_up_to(3), expected);
+        assert_eq!(automata.words_up_to(1), expected[..1].to_vec());
+        assert!(automata.words_up_to(0).is_empty());
+
+        let automata = crate::regex::from_regex("b*|a").unwrap();
+        let words: Vec<_> = automata
+            .words_up_to(2)
+            .into_iter()
+            .map(|word| word.concat())
+            .collect();
+        assert_eq!(words, vec!["", "a", "b", "bb"]);
+    }
+
+    #[test]
+    fn language_size() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert_eq!(automata.language_size(), Some(3));
+
+        let automata = crate::regex::from_regex("(a|b)(a|b|)c|").unwrap();
+        assert_eq!(automata.language_size(), Some(1 + 2 + 4));
+
+        let automata = crate::regex::from_regex("ab*").unwrap();
+        assert_eq!(automata.language_size(), None);
+
+        // 2^64 words of length 64
+        let automata = crate::regex::from_regex(&"(a|b)".repeat(64)).unwrap();
+        assert_eq!(automata.language_size(), Some(u64::MAX));
+
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 0));
+        assert_eq!(automata.language_size(), Some(0));
+    }
+
+    #[test]
+    fn shortest_accepted() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert_eq!(automata.shortest_accepted(), Some(vec!["a".to_owned()]));
+
+        let automata = crate::regex::from_regex("(a|b)*abb").unwrap();
+        let word = automata.shortest_accepted().unwrap();
+        assert_eq!(word.concat(), "abb");
+        assert_eq!(automata.to_deterministic().shortest_accepted(), Some(word));
+
+        let automata = crate::regex::from_regex("a*").unwrap();
+        assert_eq!(automata.shortest_accepted(), Some(Vec::new()));
+
+        // the accept state can't be reached
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 0));
+        automata.push_transition_from(1, Transition::new("a", 0));
+        assert_eq!(automata.shortest_accepted(), None);
+        assert_eq!(Automata::new().shortest_accepted(), None);
+    }
+
+    #[test]
+    fn display_sorted_accept_states() {
+        // accept states pushed out of order
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for state in &["0", "1", "2", "3"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_transition_from(0, Transition::new("b", 3));
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        automata.push_accept_state(3);
+        automata.push_accept_state(1);
+        automata.push_accept_state(2);
+
+        assert_eq!(automata.accept_states, vec![3, 1, 2]);
+        let output = format!("{}", automata);
+        assert!(output.contains("Estados de aceptación\n1 2 3 \n"));
+
+        let afd_automata = automata.to_deterministic_with(TrapPolicy::None);
+        assert_eq!(afd_automata.accept_states, vec![1, 2, 3]);
+        let afd_automata = automata.to_deterministic_stats().0;
+        let output = format!("{}", afd_automata);
+        assert!(output.contains("Estados de aceptación\n1 2 3 \n"));
+    }
+
+    #[test]
+    fn is_empty_language() {
+        assert!(Automata::new().is_empty_language());
+
+        // 2 accepts, but only 1 leads there
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        for state in &["0", "1", "2"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_accept_state(2);
+        automata.push_transition_from(0, Transition::new("a", 0));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        assert!(!automata.is_empty());
+        assert!(automata.is_empty_language());
+        assert_eq!(automata.shortest_accepted(), None);
+
+        automata.push_transition_from(0, Transition::epsilon(1));
+        assert!(!automata.is_empty_language());
+        assert!(!automata.to_deterministic().is_empty_language());
+        assert!(!automata.complement().is_empty_language());
+        assert!(automata
+            .intersection(&automata.complement())
+            .is_empty_language());
+    }
+
+    #[test]
+    fn shared_alphabet() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+        let minimal = automata.minimize();
+        assert!(Arc::ptr_eq(&automata.alphabet, &afd_automata.alphabet));
+        assert!(Arc::ptr_eq(&automata.alphabet, &minimal.alphabet));
+        assert!(Arc::ptr_eq(
+            &automata.alphabet,
+            &automata.reverse().alphabet
+        ));
+        assert!(automata.equivalent(&afd_automata));
+        assert!(automata.equivalent(&minimal));
+
+        // changing the alphabet of one of them leaves the others alone
+        let mut extended = afd_automata.clone();
+        extended.push_symbol("c");
+        assert!(!Arc::ptr_eq(&automata.alphabet, &extended.alphabet));
+        assert_eq!(*automata.alphabet, ["a", "b"]);
+        assert_eq!(*extended.alphabet, ["a", "b", "c"]);
+        assert_eq!(afd_automata.to_deterministic().alphabet.len(), 2);
+    }
+
+    #[test]
+    fn is_finite_language() {
+        let automata = crate::regex::from_regex("a*").unwrap();
+        assert!(!automata.is_finite_language());
+        assert!(!automata.to_deterministic().is_finite_language());
+
+        let automata = crate::regex::from_regex("abc").unwrap();
+        assert!(automata.is_finite_language());
+        assert!(automata.to_deterministic().is_finite_language());
+        assert!(FileParser::with_filename("tests/afn1")
+            .parse()
+            .unwrap()
+            .is_finite_language());
+
+        // "ab" with an epsilon cycle between 0 and 1, and a cycle on "a" that
+        // can't reach the accept state
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for state in &["0", "1", "2", "3", "4"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_transition_from(0, Transition::epsilon(1));
+        automata.push_transition_from(1, Transition::epsilon(0));
+        automata.push_transition_from(1, Transition::new("a", 2));
+        automata.push_transition_from(2, Transition::new("b", 3));
+        automata.push_transition_from(2, Transition::new("a", 4));
+        automata.push_transition_from(4, Transition::new("a", 4));
+        automata.push_accept_state(3);
+        assert!(automata.is_finite_language());
+        assert_eq!(automata.language_size(), Some(1));
+
+        automata.push_transition_from(3, Transition::epsilon(0));
+        assert!(!automata.is_finite_language());
+
+        assert!(Automata::new().is_finite_language());
+    }
+
+    #[test]
+    fn empty_alphabet() {
+        let mut automata = Automata::new();
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_transition_from(0, Transition::epsilon(1));
+
+        assert!(!automata.accepts(&[]));
+        assert!(!automata.accepts(&["a"]));
+
+        automata.push_accept_state(1);
+        assert!(automata.accepts(&[]));
+        assert!(!automata.accepts(&["a"]));
+        assert_eq!(
+            automata.accepts_consumed(&["a", "b"], usize::MAX, &automata.dead_mask()),
+            Ok((false, 0))
+        );
+        assert!(automata.to_deterministic().accepts(&[]));
+        assert!(!automata.to_deterministic().accepts(&["a"]));
+
+        assert_eq!(automata.tokenize(""), Some(Vec::new()));
+        assert_eq!(automata.tokenize("a"), None);
+        let report = automata.classify_report(&[""], &["a"]);
+        assert!(report.passed());
+
+        // a default transition reads any symbol
+        automata.set_default_transition(1, 1);
+        assert!(automata.accepts(&["a"]));
+    }
+
+    #[test]
+    fn to_regex() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_accept_state(1);
+        automata.push_transition_from(0, Transition::new("a", 1));
+        automata.push_transition_from(1, Transition::new("b", 1));
+
+        let regex = automata.to_regex();
+        assert_eq!(regex, "ab*");
+        assert!(crate::regex::from_regex(&regex)
+            .unwrap()
+            .equivalent(&automata));
+
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("0".into());
+        automata.push_accept_state(0);
+        automata.push_transition_from(0, Transition::new("a", 0));
+        assert_eq!(automata.to_regex(), "a*");
+
+        automata.transitions[0].clear();
+        assert_eq!(automata.to_regex(), "");
+        automata.accept_states.clear();
+        assert_eq!(automata.to_regex(), "∅");
+
+        for pattern in &["(a|b)*abb", "a(b|)c*|ba", "((ab)*|c)*"] {
+            let automata = crate::regex::from_regex(pattern).unwrap();
+            let regex = automata.to_regex();
+            let parsed = crate::regex::from_regex(&regex).unwrap();
+            assert!(parsed.equivalent(&automata), "{} gave {}", pattern, regex);
+        }
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let parsed = crate::regex::from_regex(&automata.to_regex()).unwrap();
+        assert!(parsed.equivalent(&automata));
+    }
+
+    #[test]
+    fn dfa_state_for() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let (afd_automata, subsets) = automata.to_deterministic_with_subsets();
+        for (index, subset) in subsets.iter().enumerate() {
+            assert_eq!(afd_automata.dfa_state_for(subset), Some(index));
+        }
+
+        let entry_subset = automata.e_closure_set(&[automata.entry_state]);
+        assert_eq!(afd_automata.dfa_state_for(&entry_subset), Some(0));
+        let trap = afd_automata.find(&"!".into()).unwrap();
+        assert_eq!(afd_automata.dfa_state_for(&[]), Some(trap));
+        assert_eq!(afd_automata.dfa_state_for(&[0]), None);
+        assert_eq!(automata.dfa_state_for(&entry_subset), None);
+
+        // already deterministic, every state stands for itself
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_state("p".into());
+        automata.push_state("q".into());
+        automata.push_transition_from(1, Transition::new("a", 0));
+        automata.push_accept_state(0);
+        automata.set_entry_state(1);
+        let afd_automata = automata.to_deterministic();
+        assert_eq!(afd_automata.dfa_state_for(&[1]), Some(0));
+        assert_eq!(afd_automata.dfa_state_for(&[0]), Some(1));
+        assert_eq!(afd_automata.dfa_state_for(&[0, 1]), None);
+
+        let mut relabeled = afd_automata.clone();
+        relabeled.relabel_with(&["p", "q", "r"]).unwrap();
+        assert_eq!(relabeled.dfa_state_for(&[1]), None);
+
+        let mut merged = afd_automata.clone();
+        merged.merge(&automata);
+        assert_eq!(merged.dfa_state_for(&[1]), None);
+    }
+
+    #[test]
+    fn determinize_no_states() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        assert!(automata.to_deterministic().is_empty());
+        assert_eq!(automata.to_deterministic().alphabet.len(), 1);
+        assert!(automata.to_deterministic_stats().0.is_empty());
+        assert!(automata.minimize().is_empty());
+        assert!(automata.complement().accepts(&["a"]));
+        assert_eq!(
+            automata.to_deterministic_with_subsets().1,
+            Vec::<Vec<usize>>::new()
+        );
+
+        let other = FileParser::with_filename("tests/afn1").parse().unwrap();
+        assert!(automata.intersection(&other).is_empty());
+        assert!(other.intersection(&automata).is_empty());
+        assert!(automata.equivalent(&Automata::new()));
+    }
+
+    #[test]
+    fn to_deterministic_merged_tags() {
+        // the states of a DFA hold several tags, so the combinators below give
+        // NFA states with more than one tag
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+        let minimal = automata.minimize();
+        assert!(afd_automata.states.iter().any(|state| state.len() > 1));
+
+        let union = afd_automata.union(&afd_automata).to_deterministic();
+        assert!(union.equivalent(&automata));
+
+        let concat = afd_automata.concat(&afd_automata).to_deterministic();
+        assert!(concat.accepts(&["a", "b", "a"]));
+        assert!(!concat.accepts(&["a", "b"]));
+        assert!(concat.equivalent(&automata.concat(&automata)));
+
+        let star = minimal.kleene_star().to_deterministic();
+        assert!(star.accepts(&[]));
+        assert!(star.accepts(&["a", "a", "b", "a"]));
+        assert!(!star.accepts(&["b"]));
+
+        let reverse = afd_automata.reverse().to_deterministic();
+        for word in words(&["a", "b"], 4) {
+            let reversed: Vec<_> = word.iter().rev().cloned().collect();
+            assert_eq!(reverse.accepts(&word), automata.accepts(&reversed));
+        }
+
+        let (stats_automata, _) = afd_automata.to_deterministic_stats();
+        assert!(stats_automata.equivalent(&automata));
+        assert!(afd_automata.equivalent(&minimal));
+        assert_eq!(afd_automata.language_size(), Some(3));
+        let regex = crate::regex::from_regex(&afd_automata.to_regex()).unwrap();
+        assert!(regex.equivalent(&automata));
+    }
+
+    #[test]
+    #[should_panic(expected = "Merged states disagree on their default transition")]
+    fn rebuild_conflicting_defaults() {
+        let mut automata = Automata::new();
+        for state in &["0", "1", "2"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.set_default_transition(0, 2);
+        automata.rebuild(&[Some(0), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn automata_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Automata>();
+    }
+
+    #[test]
+    fn canonical_dfa() {
+        // (a|b)*abb with an unreachable state, a dead end and an epsilon cycle
+        let mut automata = crate::regex::from_regex("(a|b)*abb").unwrap();
+        let len = automata.len();
+        for state in &["unreachable", "dead"] {
+            automata.push_state((*state).into());
+        }
+
+        automata.push_transition_from(len, Transition::new("a", automata.entry_state));
+        automata.push_transition_from(automata.entry_state, Transition::new("b", len + 1));
+        automata.push_transition_from(len + 1, Transition::epsilon(len + 1));
+
+        let canonical = automata.canonical_dfa();
+        assert!(canonical.is_minimal());
+        assert!(canonical.equivalent(&automata));
+        assert_eq!(canonical.len(), 4);
+        for index in 0..canonical.len() {
+            assert_eq!(canonical[index], State::from(index.to_string().as_str()));
+        }
+
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let canonical = automata.canonical_dfa();
+        assert!(canonical.is_minimal());
+        assert!(canonical.equivalent(&automata));
+        assert!(Automata::new().canonical_dfa().is_empty());
+    }
+
+    #[test]
+    fn classify_report_matches_accepts() {
+        // {a, aa, ba}, the DFA has a trap state
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let afd_automata = automata.to_deterministic();
+        assert!(!afd_automata.dead_states().is_empty());
+
+        for automata in &[automata, afd_automata] {
+            let all: Vec<String> = words(&["a", "b"], 4).iter().map(|w| w.concat()).collect();
+            let (accepted, rejected): (Vec<&str>, Vec<&str>) = all
+                .iter()
+                .map(String::as_str)
+                .partition(|word| automata.accepts(&automata.tokenize(word).unwrap()));
+
+            assert_eq!(accepted, vec!["a", "aa", "ba"]);
+            assert!(automata.classify_report(&accepted, &rejected).passed());
+        }
+    }
+
+    #[test]
+    fn to_deterministic_optimized() {
+        // a*b, with the loop on a spread over a cycle of 20 epsilon moves
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        for index in 0..21 {
+            automata.push_state(index.to_string().into());
+        }
+
+        for index in 0..20 {
+            automata.push_transition_from(index, Transition::epsilon((index + 1) % 20));
+            automata.push_transition_from(index, Transition::new("a", (index + 7) % 20));
+        }
+
+        automata.push_transition_from(19, Transition::new("b", 20));
+        automata.push_accept_state(20);
+
+        let collapsed = automata.collapse_epsilon_cycles();
+        assert_eq!(collapsed.len(), 2);
+        assert!(!collapsed.has_epsilon());
+        assert!(collapsed.equivalent(&automata));
+
+        let afd_automata = automata.to_deterministic_optimized();
+        assert!(afd_automata.equivalent(&automata));
+        assert_eq!(
+            afd_automata.len(),
+            automata.to_deterministic().minimize().len()
+        );
+        for word in words(&["a", "b"], 4) {
+            assert_eq!(afd_automata.accepts(&word), automata.accepts(&word));
+        }
+
+        assert!(afd_automata.accepts(&["a", "a", "b"]));
+        assert!(!afd_automata.accepts(&["b", "a"]));
+
+        // a state with a default transition stays apart
+        automata.set_default_transition(3, 20);
+        let collapsed = automata.collapse_epsilon_cycles();
+        assert_eq!(collapsed.len(), 3);
+        assert!(collapsed.equivalent(&automata));
+        assert!(automata.to_deterministic_optimized().equivalent(&automata));
+
+        assert!(Automata::new().to_deterministic_optimized().is_empty());
+    }
+
+    #[test]
+    fn reverse_default_transitions() {
+        let mut automata = Automata::new();
+        automata.push_symbol("a");
+        automata.push_symbol("b");
+        automata.push_state("0".into());
+        automata.push_state("1".into());
+        automata.push_state("2".into());
+        automata.push_accept_state(1);
+        automata.set_default_transition(0, 1);
+        automata.push_transition_from(0, Transition::new("b", 2));
+        automata.push_transition_from(2, Transition::new("b", 1));
+        assert!(automata.accepts(&["a"]));
+
+        // "a" through the default, "bb" through the explicit edges
+        let reversed = automata.reverse();
+        assert!(reversed.accepts(&["a"]));
+        assert!(reversed.accepts(&["b", "b"]));
+        assert!(!reversed.accepts(&["b"]));
+        for word in words(&["a", "b"], 4) {
+            let backwards: Vec<_> = word.iter().rev().cloned().collect();
+            assert_eq!(reversed.accepts(&word), automata.accepts(&backwards));
+        }
     }
 }
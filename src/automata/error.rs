@@ -0,0 +1,45 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutomataError {
+    IndexOutOfBounds { index: usize, len: usize },
+    DuplicateSymbol(String),
+    EpsilonSymbol(String),
+    LengthMismatch { expected: usize, found: usize },
+    DuplicateTag(String),
+    MissingSymbol(String),
+    EmptyLanguage,
+    StateSetTooLarge { size: usize, limit: usize },
+}
+
+impl fmt::Display for AutomataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AutomataError::IndexOutOfBounds { index, len } => {
+                write!(f, "Index {} out of bounds (length is {})", index, len)
+            }
+            AutomataError::DuplicateSymbol(symbol) => {
+                write!(f, "Symbol {:?} is already in the alphabet", symbol)
+            }
+            AutomataError::EpsilonSymbol(symbol) => {
+                write!(f, "Symbol {:?} is reserved for epsilon", symbol)
+            }
+            AutomataError::LengthMismatch { expected, found } => {
+                write!(f, "Expected {} items, found {}", expected, found)
+            }
+            AutomataError::DuplicateTag(tag) => {
+                write!(f, "Tag {:?} is used more than once", tag)
+            }
+            AutomataError::MissingSymbol(symbol) => {
+                write!(f, "Symbol {:?} is missing", symbol)
+            }
+            AutomataError::EmptyLanguage => write!(f, "The automaton accepts no word"),
+            AutomataError::StateSetTooLarge { size, limit } => {
+                write!(f, "Reached {} active states (limit is {})", size, limit)
+            }
+        }
+    }
+}
+
+impl Error for AutomataError {}
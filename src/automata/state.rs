@@ -2,7 +2,7 @@ use std::collections::BTreeSet;
 
 pub type Set<T> = BTreeSet<T>;
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct State {
     tags: Set<String>,
 }
@@ -15,6 +15,22 @@ impl State {
     pub fn tags(&self) -> &Set<String> {
         &self.tags
     }
+
+    pub fn contains_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    pub fn label(&self) -> String {
+        self.tags.iter().cloned().collect::<Vec<_>>().join(",")
+    }
 }
 
 impl From<Set<String>> for State {
@@ -48,3 +64,19 @@ impl From<String> for State {
         State { tags }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_tags() {
+        let state = State::from(&["q0", "q2", "q5"][..]);
+
+        assert!(state.contains_tag("q2"));
+        assert!(!state.contains_tag("q1"));
+        assert_eq!(state.len(), 3);
+        assert!(!state.is_empty());
+        assert!(State::new().is_empty());
+    }
+}
@@ -1,7 +1,8 @@
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default)]
 pub struct Transition {
     symbol: String,
     end_state: usize,
+    weight: Option<f64>,
 }
 
 impl Transition {
@@ -9,9 +10,26 @@ impl Transition {
         Self {
             symbol: symbol.to_owned(),
             end_state: end,
+            weight: None,
         }
     }
 
+    pub fn epsilon(end: usize) -> Self {
+        Self::new("", end)
+    }
+
+    /// A transition carrying a weight, ignored by everything but `path_weight`.
+    pub fn weighted(symbol: &str, end: usize, weight: f64) -> Self {
+        Self {
+            weight: Some(weight),
+            ..Self::new(symbol, end)
+        }
+    }
+
+    pub fn is_epsilon(&self) -> bool {
+        self.symbol.is_empty()
+    }
+
     pub fn symbol(&self) -> &str {
         &self.symbol
     }
@@ -19,4 +37,19 @@ impl Transition {
     pub fn end_state(&self) -> usize {
         self.end_state
     }
+
+    pub fn weight(&self) -> Option<f64> {
+        self.weight
+    }
 }
+
+// weights are compared bit by bit, so equality stays reflexive
+impl PartialEq for Transition {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol
+            && self.end_state == other.end_state
+            && self.weight.map(f64::to_bits) == other.weight.map(f64::to_bits)
+    }
+}
+
+impl Eq for Transition {}
@@ -1,2 +1,8 @@
 pub mod automata;
 pub mod parser;
+pub mod regex;
+
+pub use automata::state::State;
+pub use automata::transition::Transition;
+pub use automata::Automata;
+pub use parser::{AutomataParser, FileParser};
@@ -1,7 +1,4 @@
-mod automata;
-mod parser;
-
-use parser::{AutomataParser, FileParser};
+use lab5::{AutomataParser, FileParser};
 use std::env::args;
 use std::fs::write;
 
@@ -16,8 +13,8 @@ fn main() {
     let output_file = &args[2];
 
     let parser = FileParser::with_filename(input_file);
-    let automata = parser.parse();
+    let automata = parser.parse().unwrap();
 
     let automata = automata.to_deterministic();
-    write(output_file, &format!("{}", automata)).expect("Failed to write");
+    write(output_file, format!("{}", automata)).expect("Failed to write");
 }
@@ -1,16 +1,109 @@
+use crate::automata::state::{Set, State};
 use crate::automata::transition::Transition;
 use crate::automata::Automata;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
+use std::num::IntErrorKind;
 use std::path::Path;
 
+pub mod dot;
+
 pub trait AutomataParser {
-    fn parse(&self) -> Automata;
+    fn parse(&self) -> Result<Automata, ParseError>;
+}
+
+/// Why an input couldn't be parsed, `line` counts from 1.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    BadHeader {
+        line: usize,
+    },
+    BadNumber {
+        line: usize,
+    },
+    CountTooLarge {
+        line: usize,
+        count: usize,
+    },
+    CountMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    UnknownState {
+        line: usize,
+        state: String,
+    },
+    TransitionArity {
+        line: usize,
+        found: usize,
+    },
+    ReservedSymbol {
+        line: usize,
+    },
+    InvalidDot(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(error) => write!(f, "I/O error: {}", error),
+            ParseError::BadHeader { line } => write!(f, "Unexpected line {}", line),
+            ParseError::BadNumber { line } => {
+                write!(f, "Failed to convert number at line {}", line)
+            }
+            ParseError::CountTooLarge { line, count } => {
+                write!(f, "Declared count too large at line {}: {}", line, count)
+            }
+            ParseError::CountMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} items at line {}, found {}",
+                expected, line, found
+            ),
+            ParseError::UnknownState { line, state } => {
+                write!(f, "Unknown state {:?} at line {}", state, line)
+            }
+            ParseError::TransitionArity { line, found } => write!(
+                f,
+                "Transition at line {} has {} fields instead of 3",
+                line, found
+            ),
+            ParseError::ReservedSymbol { line } => {
+                write!(f, "Symbol -1 at line {} is reserved for epsilon", line)
+            }
+            ParseError::InvalidDot(message) => write!(f, "Invalid DOT input: {}", message),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+pub const DEFAULT_MAX_COUNT: usize = 1 << 20;
+
+#[derive(Debug, Clone)]
 pub struct FileParser {
     filename: String,
+    max_count: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -18,6 +111,7 @@ enum Expecting {
     Nothing,
     NumberOfStates,
     States,
+    EntryState,
     NumberOfAcceptStates,
     AcceptStates,
     NumberOfSymbols,
@@ -26,6 +120,15 @@ enum Expecting {
     Transitions,
 }
 
+impl Default for FileParser {
+    fn default() -> Self {
+        Self {
+            filename: String::new(),
+            max_count: DEFAULT_MAX_COUNT,
+        }
+    }
+}
+
 impl FileParser {
     pub fn new() -> Self {
         Default::default()
@@ -34,46 +137,112 @@ impl FileParser {
     pub fn with_filename(filename: &str) -> Self {
         Self {
             filename: filename.to_owned(),
+            ..Default::default()
         }
     }
 
     pub fn change_filename(&mut self, filename: &str) {
         self.filename = filename.to_owned();
     }
-}
 
-impl AutomataParser for FileParser {
-    fn parse(&self) -> Automata {
-        let path = Path::new(&self.filename)
-            .canonicalize()
-            .expect("Failed to canonicalize");
-
-        let file =
-            File::open(&path).unwrap_or_else(|_| panic!("Failed to open file in path: {:?}", path));
-        let reader = BufReader::new(file);
+    pub fn change_max_count(&mut self, max_count: usize) {
+        self.max_count = max_count;
+    }
+
+    fn parse_count(&self, number: usize, line: &str) -> Result<usize, ParseError> {
+        let count = match line.parse::<usize>() {
+            Ok(count) => count,
+            Err(ref e) if *e.kind() == IntErrorKind::PosOverflow => usize::MAX,
+            Err(_) => return Err(ParseError::BadNumber { line: number }),
+        };
+
+        if count > self.max_count {
+            return Err(ParseError::CountTooLarge {
+                line: number,
+                count,
+            });
+        }
+
+        Ok(count)
+    }
+
+    /// Reads either the counted format, where states are referred to by name,
+    /// or the one written by `Display`, with no counts and `i = { tags }` state
+    /// lines whose index `i` is used to refer to them. The entry is the first
+    /// state unless an "Estado inicial" section names another one.
+    fn parse_from(&self, reader: impl BufRead) -> Result<Automata, ParseError> {
         let mut expecting = Expecting::Nothing;
         let mut total_found = 0;
         let mut total_expected = 0;
+        let mut indexed = false;
 
         let mut automata = Automata::new();
-        for line in reader.lines() {
-            let line = line.expect("Failed to get line");
+        for (number, line) in reader.lines().enumerate() {
+            let number = number + 1;
+            let line = line?;
+            let unknown_state = |state: &str| ParseError::UnknownState {
+                line: number,
+                state: state.to_owned(),
+            };
+
+            let find = |automata: &Automata, state: &str| {
+                let index = if indexed {
+                    state.parse().ok().filter(|&index| index < automata.len())
+                } else {
+                    automata.find(&state.into())
+                };
+
+                index.ok_or_else(|| unknown_state(state))
+            };
+
             match line.trim() {
                 "Estados" => expecting = Expecting::NumberOfStates,
-                "Estados de aceptación" => expecting = Expecting::NumberOfAcceptStates,
+                "Estado inicial" => expecting = Expecting::EntryState,
+                // older files spell the header double encoded
+                "Estados de aceptación" | "Estados de aceptaciÃ³n" if indexed => {
+                    expecting = Expecting::AcceptStates
+                }
+                "Estados de aceptación" | "Estados de aceptaciÃ³n" => {
+                    expecting = Expecting::NumberOfAcceptStates
+                }
+                "Alfabeto" if indexed => expecting = Expecting::Symbols,
                 "Alfabeto" => expecting = Expecting::NumberOfSymbols,
+                "Transiciones" if indexed => {
+                    expecting = Expecting::Transitions;
+                    total_expected = usize::MAX;
+                }
                 "Transiciones" => expecting = Expecting::NumberOfTransitions,
                 "" => continue,
                 line => match expecting {
+                    Expecting::NumberOfStates | Expecting::States if is_state_definition(line) => {
+                        expecting = Expecting::States;
+                        indexed = true;
+                        total_expected = 0;
+
+                        let (index, tags) = line.split_at(line.find('=').unwrap());
+                        if index.trim() != automata.len().to_string() {
+                            return Err(unknown_state(index.trim()));
+                        }
+
+                        let tags = tags[1..].trim();
+                        let tags = tags[1..tags.len() - 1].split_ascii_whitespace();
+                        automata
+                            .push_state(State::from(tags.map(str::to_owned).collect::<Set<_>>()));
+                    }
+
                     Expecting::NumberOfStates => {
                         expecting = Expecting::States;
-                        total_expected = line.parse().expect("Failed to convert number")
+                        total_expected = self.parse_count(number, line)?;
                     }
 
                     Expecting::States => {
                         let states: Vec<_> = line.split_ascii_whitespace().collect();
                         if total_expected > states.len() {
-                            panic!("Too much/few states!");
+                            return Err(ParseError::CountMismatch {
+                                line: number,
+                                expected: total_expected,
+                                found: states.len(),
+                            });
                         }
 
                         for state in states {
@@ -81,84 +250,342 @@ impl AutomataParser for FileParser {
                         }
                     }
 
+                    Expecting::EntryState => {
+                        let index = find(&automata, line)?;
+                        automata.set_entry_state(index);
+                    }
+
                     Expecting::NumberOfAcceptStates => {
                         expecting = Expecting::AcceptStates;
-                        total_expected = line.parse().expect("Failed to convert number")
+                        total_expected = self.parse_count(number, line)?;
                     }
 
                     Expecting::AcceptStates => {
                         let states: Vec<_> = line.split_ascii_whitespace().collect();
                         if total_expected > states.len() {
-                            panic!("Too much/few states!");
+                            return Err(ParseError::CountMismatch {
+                                line: number,
+                                expected: total_expected,
+                                found: states.len(),
+                            });
                         }
 
                         for state in states {
-                            if let Some(state) = automata.find(&state.into()) {
-                                automata.push_accept_state(state)
-                            } else {
-                                panic!("Unknown state given!");
-                            }
+                            let index = find(&automata, state)?;
+                            automata.push_accept_state(index);
                         }
                     }
 
                     Expecting::NumberOfSymbols => {
                         expecting = Expecting::Symbols;
-                        total_expected = line.parse().expect("Failed to convert number")
+                        total_expected = self.parse_count(number, line)?;
                     }
 
                     Expecting::Symbols => {
                         let symbols: Vec<_> = line.split_ascii_whitespace().collect();
                         if total_expected > symbols.len() {
-                            panic!("Too much/few symbols!");
+                            return Err(ParseError::CountMismatch {
+                                line: number,
+                                expected: total_expected,
+                                found: symbols.len(),
+                            });
                         }
 
                         for symbol in symbols {
-                            automata.push_symbol(&symbol);
+                            if symbol == "-1" {
+                                return Err(ParseError::ReservedSymbol { line: number });
+                            }
+
+                            automata.push_symbol(&unescape_symbol(symbol));
                         }
                     }
 
                     Expecting::NumberOfTransitions => {
                         expecting = Expecting::Transitions;
-                        total_expected = line.parse().expect("Failed to convert number")
+                        total_expected = self.parse_count(number, line)?;
                     }
 
                     Expecting::Transitions => {
                         if total_found == total_expected {
-                            panic!("Too much states!");
+                            return Err(ParseError::CountMismatch {
+                                line: number,
+                                expected: total_expected,
+                                found: total_found + 1,
+                            });
                         }
 
                         let transition_line: Vec<_> = line.split_ascii_whitespace().collect();
                         if transition_line.len() != 3 {
-                            panic!("Transition syntax error!");
+                            return Err(ParseError::TransitionArity {
+                                line: number,
+                                found: transition_line.len(),
+                            });
                         }
 
                         let symbol = if transition_line[1] == "-1" {
-                            ""
+                            String::new()
                         } else {
-                            transition_line[1]
+                            unescape_symbol(transition_line[1])
                         };
 
-                        if let Some(beg_state) = automata.find(&transition_line[0].into()) {
-                            if let Some(end_state) = automata.find(&transition_line[2].into()) {
-                                automata.push_transition_from(
-                                    beg_state,
-                                    Transition::new(symbol, end_state),
-                                );
-                            } else {
-                                panic!("Unkown end state given!");
-                            }
-                        } else {
-                            panic!("Unknown begin state given!");
-                        }
+                        let beg_state = find(&automata, transition_line[0])?;
+                        let end_state = find(&automata, transition_line[2])?;
+                        automata
+                            .push_transition_from(beg_state, Transition::new(&symbol, end_state));
 
                         total_found += 1;
                     }
 
-                    Expecting::Nothing => panic!("Unexpected line"),
+                    Expecting::Nothing => return Err(ParseError::BadHeader { line: number }),
                 },
             }
         }
 
-        automata
+        Ok(automata)
+    }
+}
+
+fn is_state_definition(line: &str) -> bool {
+    match line.find('=') {
+        Some(position) => {
+            let tags = line[position + 1..].trim();
+            tags.starts_with('{') && tags.ends_with('}')
+        }
+        None => false,
+    }
+}
+
+/// Escapes the characters the text format can't hold in a symbol: whitespace
+/// (`\s`, `\t`, `\n`, `\r`) and the backslash itself (`\\`).
+pub fn escape_symbol(symbol: &str) -> String {
+    let mut escaped = String::with_capacity(symbol.len());
+    for c in symbol.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Reverts `escape_symbol`, unknown escapes are kept as they are.
+pub fn unescape_symbol(symbol: &str) -> String {
+    let mut unescaped = String::with_capacity(symbol.len());
+    let mut chars = symbol.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('s') => unescaped.push(' '),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+impl AutomataParser for FileParser {
+    fn parse(&self) -> Result<Automata, ParseError> {
+        let path = Path::new(&self.filename).canonicalize()?;
+        let file = File::open(path)?;
+        self.parse_from(BufReader::new(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automata::Acceptance;
+
+    #[test]
+    fn whitespace_symbols() {
+        let input = "Estados\n2\n0 1\n\n\
+                     Estados de aceptación\n1\n1\n\n\
+                     Alfabeto\n3\n\\s \\t a\\\\b\n\n\
+                     Transiciones\n3\n0 \\s 1\n1 \\t 0\n0 -1 1\n";
+        let automata = FileParser::new().parse_from(input.as_bytes()).unwrap();
+
+        assert!(automata.accepts(&[" "]));
+        assert!(automata.accepts(&[" ", "\t", " "]));
+        assert_eq!(automata.accepts_detailed(&["a\\b"]), Acceptance::Rejected);
+
+        let output = format!("{}", automata);
+        assert!(output.contains("Alfabeto\n\\s \\t a\\\\b \n"));
+        assert!(output.contains("0 \\s 1\n"));
+        assert!(output.contains("1 \\t 0\n"));
+        assert!(output.contains("0 -1 1\n"));
+
+        for symbol in &[" ", "\t", "a b\\", "\\s", "-1"] {
+            assert_eq!(&unescape_symbol(&escape_symbol(symbol)), symbol);
+        }
+    }
+
+    #[test]
+    fn count_too_large() {
+        let input = "Estados\n999999999\n0 1\n";
+        match FileParser::new().parse_from(input.as_bytes()) {
+            Err(ParseError::CountTooLarge { line: 2, count }) => assert_eq!(count, 999999999),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn count_overflows() {
+        let input = "Alfabeto\n99999999999999999999999999\na\n";
+        match FileParser::new().parse_from(input.as_bytes()) {
+            Err(ParseError::CountTooLarge { line: 2, count }) => assert_eq!(count, usize::MAX),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn configurable_max_count() {
+        let input = "Estados\n2\nq0 q1\n\nAlfabeto\n1\na\n";
+        let mut parser = FileParser::new();
+        parser.change_max_count(2);
+
+        let automata = parser.parse_from(input.as_bytes()).unwrap();
+        assert_eq!(automata.len(), 2);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |input: &str| FileParser::new().parse_from(input.as_bytes());
+
+        match parse("Estados\n2\n0 1\n\nAlfabeto\nx\n") {
+            Err(ParseError::BadNumber { line: 6 }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match parse("Estados\n3\n0 1\n") {
+            Err(ParseError::CountMismatch {
+                line: 3,
+                expected: 3,
+                found: 2,
+            }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match parse("Estados\n2\n0 1\nEstados de aceptación\n1\n2\n") {
+            Err(ParseError::UnknownState { line: 6, state }) => assert_eq!(state, "2"),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match parse("Estados\n2\n0 1\nTransiciones\n2\n0 a 1\n1 a\n") {
+            Err(ParseError::TransitionArity { line: 7, found: 2 }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match parse("Transiciones\n1\n0 a 1\n") {
+            Err(ParseError::UnknownState { line: 3, state }) => assert_eq!(state, "0"),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match parse("0 1\n") {
+            Err(ParseError::BadHeader { line: 1 }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        match FileParser::with_filename("tests/missing").parse() {
+            Err(ParseError::Io(_)) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn display_roundtrip() {
+        let automata = FileParser::with_filename("tests/afn1").parse().unwrap();
+        let output = format!("{}", automata);
+        assert!(output.contains("0 -1 2\n"));
+
+        let parsed = FileParser::new().parse_from(output.as_bytes()).unwrap();
+        assert_eq!(format!("{}", parsed), output);
+        assert_eq!(parsed.transition_relation(), automata.transition_relation());
+        assert!(parsed.accepts(&["b", "a"]));
+
+        let afd_automata = automata.to_deterministic();
+        let output = format!("{}", afd_automata);
+        let parsed = FileParser::new().parse_from(output.as_bytes()).unwrap();
+        assert_eq!(format!("{}", parsed), output);
+        assert_eq!(parsed[0], State::from(&["0", "2", "3", "4"][..]));
+
+        let parsed = FileParser::with_filename("tests/afd1").parse().unwrap();
+        assert_eq!(format!("{}", parsed), output);
+        assert!(parsed.equivalent(&automata));
+    }
+
+    #[test]
+    fn display_roundtrip_entry_state() {
+        let automata = crate::regex::from_regex("a|bc").unwrap();
+        let reversed = automata.reverse();
+        for automata in &[automata, reversed] {
+            let output = format!("{}", automata);
+            assert!(output.contains("Estado inicial\n"));
+
+            let parsed = FileParser::new().parse_from(output.as_bytes()).unwrap();
+            assert_eq!(format!("{}", parsed), output);
+            assert!(parsed.equivalent(automata));
+        }
+
+        let input = "Estados\n2\np q\n\nEstado inicial\nq\n\n\
+                     Estados de aceptación\n1\np\n\n\
+                     Alfabeto\n1\na\n\n\
+                     Transiciones\n1\nq a p\n";
+        let automata = FileParser::new().parse_from(input.as_bytes()).unwrap();
+        assert!(automata.accepts(&["a"]));
+        assert!(!automata.accepts(&[]));
+
+        match FileParser::new().parse_from("Estados\n1\np\nEstado inicial\nq\n".as_bytes()) {
+            Err(ParseError::UnknownState { line: 5, .. }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn double_encoded_header() {
+        let input = "Estados\n2\n0 1\n\n\
+                     Estados de aceptaciÃ³n\n1\n1\n\n\
+                     Alfabeto\n1\na\n\n\
+                     Transiciones\n1\n0 a 1\n";
+        let automata = FileParser::new().parse_from(input.as_bytes()).unwrap();
+        assert!(automata.accepts(&["a"]));
+
+        let output = format!("{}", automata);
+        assert!(output.contains("Estados de aceptación\n"));
+        let parsed = FileParser::new().parse_from(output.as_bytes()).unwrap();
+        assert_eq!(format!("{}", parsed), output);
+        assert!(parsed.accepts(&["a"]));
+        assert!(!parsed.accepts(&[]));
+    }
+
+    #[test]
+    fn reserved_symbol() {
+        let input = "Estados\n1\n0\n\nAlfabeto\n2\na -1\n";
+        match FileParser::new().parse_from(input.as_bytes()) {
+            Err(ParseError::ReservedSymbol { line: 7 }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let input = "0 = { q0 }\n";
+        match FileParser::new().parse_from(input.as_bytes()) {
+            Err(ParseError::BadHeader { line: 1 }) => {}
+            result => panic!("Unexpected result: {:?}", result),
+        }
     }
 }
@@ -0,0 +1,412 @@
+use super::{AutomataParser, ParseError};
+use crate::automata::state::State;
+use crate::automata::transition::Transition;
+use crate::automata::Automata;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::slice::Iter;
+
+/// Parses the small subset of the Graphviz DOT language used to draw automata.
+///
+/// Nodes with `shape=doublecircle` (given directly or through a previous
+/// `node [shape=doublecircle]`) are accept states, an edge coming from a node
+/// with `shape=point`, `shape=none` or `style=invis` marks the entry state, and
+/// every other edge `a -> b [label="x,y"]` becomes one transition per label,
+/// where `ε` or an empty label means epsilon. A node's label, split on commas
+/// (as `State::label` joins them) or whitespace, gives its tags, otherwise the
+/// node id is used.
+#[derive(Debug, Clone, Default)]
+pub struct DotParser {
+    source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Id(String),
+    Arrow,
+    Punct(char),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    label: Option<String>,
+    shape: Option<String>,
+    invisible: bool,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    order: Vec<String>,
+    nodes: HashMap<String, Node>,
+    edges: Vec<(String, String, String)>,
+    default_shape: Option<String>,
+}
+
+impl Node {
+    fn is_start(&self) -> bool {
+        let shape = self.shape.as_deref().unwrap_or("");
+        self.invisible || ["point", "none", "plaintext"].contains(&shape)
+    }
+
+    fn is_accept(&self) -> bool {
+        self.shape.as_deref() == Some("doublecircle")
+    }
+}
+
+impl Graph {
+    fn node(&mut self, id: &str) -> &mut Node {
+        if !self.nodes.contains_key(id) {
+            self.order.push(id.to_owned());
+        }
+
+        let shape = &self.default_shape;
+        self.nodes.entry(id.to_owned()).or_insert_with(|| Node {
+            shape: shape.clone(),
+            ..Default::default()
+        })
+    }
+
+    fn into_automata(self) -> Result<Automata, ParseError> {
+        let mut automata = Automata::new();
+        let mut indices = HashMap::new();
+        for id in &self.order {
+            let node = &self.nodes[id];
+            if node.is_start() {
+                continue;
+            }
+
+            let state = match &node.label {
+                Some(label) => {
+                    let tags: Vec<_> = label
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|tag| !tag.is_empty())
+                        .collect();
+                    State::from(&tags[..])
+                }
+                None => State::from(id.as_str()),
+            };
+
+            indices.insert(id.as_str(), automata.len());
+            automata.push_state(state);
+            if node.is_accept() {
+                automata.push_accept_state(automata.len() - 1);
+            }
+        }
+
+        let mut alphabet = Vec::new();
+        let mut entry_state = None;
+        for (from, to, label) in &self.edges {
+            let end_state = match indices.get(to.as_str()) {
+                Some(&end_state) => end_state,
+                None => return Err(invalid(format!("edge into start node {}", to))),
+            };
+
+            let beg_state = match indices.get(from.as_str()) {
+                Some(&beg_state) => beg_state,
+                None => {
+                    entry_state = entry_state.or(Some(end_state));
+                    continue;
+                }
+            };
+
+            for symbol in label.split(',').map(str::trim) {
+                let symbol = if symbol == "ε" { "" } else { symbol };
+                if !symbol.is_empty() && !alphabet.contains(&symbol) {
+                    alphabet.push(symbol);
+                    automata.push_symbol(symbol);
+                }
+
+                automata.push_transition_from(beg_state, Transition::new(symbol, end_state));
+            }
+        }
+
+        if let Some(entry_state) = entry_state {
+            automata.set_entry_state(entry_state);
+        }
+
+        Ok(automata)
+    }
+}
+
+impl DotParser {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_source(source: &str) -> Self {
+        Self {
+            source: source.to_owned(),
+        }
+    }
+
+    pub fn change_source(&mut self, source: &str) {
+        self.source = source.to_owned();
+    }
+}
+
+impl AutomataParser for DotParser {
+    fn parse(&self) -> Result<Automata, ParseError> {
+        let tokens = tokenize(&self.source);
+        let mut tokens = tokens.iter().peekable();
+
+        if tokens.peek() == Some(&&Token::Id("strict".to_owned())) {
+            tokens.next();
+        }
+
+        match tokens.next() {
+            Some(Token::Id(id)) if id == "digraph" => {}
+            _ => return Err(invalid("expected digraph".to_owned())),
+        }
+
+        if let Some(Token::Id(_)) = tokens.peek() {
+            tokens.next();
+        }
+
+        if tokens.next() != Some(&Token::Punct('{')) {
+            return Err(invalid("expected opening brace after digraph".to_owned()));
+        }
+
+        let mut graph = Graph::default();
+        loop {
+            let id = match tokens.next() {
+                Some(Token::Punct('}')) => break,
+                Some(Token::Punct(';')) => continue,
+                Some(Token::Id(id)) => id,
+                token => return Err(unexpected(token)),
+            };
+
+            match tokens.peek() {
+                Some(Token::Punct('=')) => {
+                    tokens.next();
+                    expect_id(&mut tokens)?;
+                }
+
+                Some(Token::Punct('[')) if ["node", "edge", "graph"].contains(&id.as_str()) => {
+                    let attributes = parse_attributes(&mut tokens)?;
+                    if id == "node" {
+                        if let Some(shape) = attributes.get("shape") {
+                            graph.default_shape = Some(shape.clone());
+                        }
+                    }
+                }
+
+                Some(Token::Arrow) => {
+                    let mut chain = vec![id.clone()];
+                    while tokens.peek() == Some(&&Token::Arrow) {
+                        tokens.next();
+                        chain.push(expect_id(&mut tokens)?);
+                    }
+
+                    let attributes = parse_attributes(&mut tokens)?;
+                    let label = attributes.get("label").cloned().unwrap_or_default();
+                    for id in &chain {
+                        graph.node(id);
+                    }
+
+                    for pair in chain.windows(2) {
+                        let edge = (pair[0].clone(), pair[1].clone(), label.clone());
+                        graph.edges.push(edge);
+                    }
+                }
+
+                _ => {
+                    let attributes = parse_attributes(&mut tokens)?;
+                    let node = graph.node(id);
+                    if let Some(label) = attributes.get("label") {
+                        node.label = Some(label.clone());
+                    }
+
+                    if let Some(shape) = attributes.get("shape") {
+                        node.shape = Some(shape.clone());
+                    }
+
+                    if attributes.get("style").map(String::as_str) == Some("invis") {
+                        node.invisible = true;
+                    }
+                }
+            }
+        }
+
+        graph.into_automata()
+    }
+}
+
+fn invalid(message: String) -> ParseError {
+    ParseError::InvalidDot(message)
+}
+
+fn unexpected(token: Option<&Token>) -> ParseError {
+    match token {
+        Some(token) => invalid(format!("unexpected token {:?}", token)),
+        None => invalid("unexpected end of input".to_owned()),
+    }
+}
+
+fn expect_id(tokens: &mut Peekable<Iter<Token>>) -> Result<String, ParseError> {
+    match tokens.next() {
+        Some(Token::Id(id)) => Ok(id.clone()),
+        token => Err(unexpected(token)),
+    }
+}
+
+fn parse_attributes(
+    tokens: &mut Peekable<Iter<Token>>,
+) -> Result<HashMap<String, String>, ParseError> {
+    let mut attributes = HashMap::new();
+    if tokens.peek() != Some(&&Token::Punct('[')) {
+        return Ok(attributes);
+    }
+
+    tokens.next();
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Punct(']')) => break,
+            Some(Token::Punct(',')) | Some(Token::Punct(';')) => continue,
+            Some(Token::Id(key)) => key.clone(),
+            token => return Err(unexpected(token)),
+        };
+
+        if tokens.next() != Some(&Token::Punct('=')) {
+            return Err(invalid(format!("expected '=' after attribute {}", key)));
+        }
+
+        attributes.insert(key, expect_id(tokens)?);
+    }
+
+    Ok(attributes)
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+
+            '/' if chars.peek() == Some(&'/') => while chars.next().is_some_and(|c| c != '\n') {},
+
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+
+                    last = c;
+                }
+            }
+
+            '#' => while chars.next().is_some_and(|c| c != '\n') {},
+
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push(Token::Arrow);
+            }
+
+            '"' => {
+                let mut id = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('"') => id.push('"'),
+                            Some('\\') => id.push('\\'),
+                            Some(c) => {
+                                id.push('\\');
+                                id.push(c);
+                            }
+                            None => break,
+                        },
+                        c => id.push(c),
+                    }
+                }
+
+                tokens.push(Token::Id(id));
+            }
+
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' => tokens.push(Token::Punct(c)),
+
+            c => {
+                let mut id = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        id.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+
+                tokens.push(Token::Id(id));
+            }
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dot() {
+        let source = r#"
+            digraph {
+                rankdir=LR;
+                node [shape=point, style=invis]; start;
+                node [shape=doublecircle]; 2 [label="q2"];
+                node [shape=circle];
+                0 [label="q0"];
+                1 [label="q1 q3"];
+                3 [label="q4,q5"];
+                start -> 0;
+                0 -> 1 [label="a,b"];
+                1 -> 1 [label="ε"];
+                1 -> 2 [label="b"];
+                2 -> 0 [label=""];
+            }
+        "#;
+
+        let automata = Automata::from_dot(source).unwrap();
+
+        assert_eq!(automata.len(), 4);
+        assert_eq!(automata.find(&(&["q4", "q5"][..]).into()), Some(3));
+        assert_eq!(automata.find(&"q2".into()), Some(0));
+        assert_eq!(automata.find(&(&["q1", "q3"][..]).into()), Some(2));
+        assert!(automata.accepts(&["a", "b"]));
+        assert!(automata.accepts(&["b", "b", "a", "b"]));
+        assert!(!automata.accepts(&["a"]));
+        assert_eq!(automata.transitions_from(0), &[Transition::epsilon(1)]);
+        assert_eq!(
+            automata.transitions_from(2),
+            &[Transition::epsilon(2), Transition::new("b", 0)]
+        );
+    }
+
+    #[test]
+    fn parse_dot_errors() {
+        let error = DotParser::with_source("graph { a -- b }")
+            .parse()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Invalid DOT input: expected digraph");
+
+        let error = DotParser::with_source("digraph { a -> b [label=] }")
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid DOT input: unexpected token Punct(']')"
+        );
+
+        let error = DotParser::with_source("digraph { a -> b")
+            .parse()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid DOT input: unexpected end of input"
+        );
+    }
+}
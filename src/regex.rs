@@ -0,0 +1,335 @@
+use crate::automata::transition::Transition;
+use crate::automata::Automata;
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegexError {
+    UnexpectedChar { position: usize, found: char },
+    UnclosedGroup { position: usize },
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegexError::UnexpectedChar { position, found } => {
+                write!(f, "Unexpected {:?} at position {}", found, position)
+            }
+            RegexError::UnclosedGroup { position } => {
+                write!(f, "Group opened at position {} is never closed", position)
+            }
+        }
+    }
+}
+
+impl Error for RegexError {}
+
+/// Builds an epsilon-NFA through Thompson's construction. The pattern is made
+/// of single character literals, concatenation, `|`, `*` and `()` groups,
+/// where any alternative may be empty. States are tagged by their index.
+pub fn from_regex(pattern: &str) -> Result<Automata, RegexError> {
+    let mut builder = Builder {
+        automata: Automata::new(),
+        symbols: Vec::new(),
+        chars: pattern.char_indices().peekable(),
+    };
+
+    let (entry_state, accept_state) = builder.alternation()?;
+    if let Some((position, found)) = builder.chars.next() {
+        return Err(RegexError::UnexpectedChar { position, found });
+    }
+
+    let mut automata = builder.automata;
+    automata.set_entry_state(entry_state);
+    automata.push_accept_state(accept_state);
+    Ok(automata)
+}
+
+/// Start and end state of a piece of the automaton.
+type Fragment = (usize, usize);
+
+struct Builder<'a> {
+    automata: Automata,
+    symbols: Vec<char>,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Builder<'_> {
+    fn new_state(&mut self) -> usize {
+        let index = self.automata.len();
+        self.automata.push_state(index.to_string().into());
+        index
+    }
+
+    fn epsilon(&mut self, from: usize, to: usize) {
+        self.automata
+            .push_transition_from(from, Transition::epsilon(to));
+    }
+
+    fn alternation(&mut self) -> Result<Fragment, RegexError> {
+        let first = self.concatenation()?;
+        if self.chars.peek().map(|&(_, c)| c) != Some('|') {
+            return Ok(first);
+        }
+
+        let (start, end) = (self.new_state(), self.new_state());
+        self.epsilon(start, first.0);
+        self.epsilon(first.1, end);
+        while self.chars.peek().map(|&(_, c)| c) == Some('|') {
+            self.chars.next();
+            let next = self.concatenation()?;
+            self.epsilon(start, next.0);
+            self.epsilon(next.1, end);
+        }
+
+        Ok((start, end))
+    }
+
+    fn concatenation(&mut self) -> Result<Fragment, RegexError> {
+        let mut fragment: Option<Fragment> = None;
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+
+            let next = self.repetition()?;
+            fragment = Some(match fragment {
+                Some((start, end)) => {
+                    self.epsilon(end, next.0);
+                    (start, next.1)
+                }
+                None => next,
+            });
+        }
+
+        match fragment {
+            Some(fragment) => Ok(fragment),
+            None => {
+                let (start, end) = (self.new_state(), self.new_state());
+                self.epsilon(start, end);
+                Ok((start, end))
+            }
+        }
+    }
+
+    fn repetition(&mut self) -> Result<Fragment, RegexError> {
+        let mut fragment = self.atom()?;
+        while self.chars.peek().map(|&(_, c)| c) == Some('*') {
+            self.chars.next();
+            let (start, end) = (self.new_state(), self.new_state());
+            self.epsilon(start, fragment.0);
+            self.epsilon(start, end);
+            self.epsilon(fragment.1, fragment.0);
+            self.epsilon(fragment.1, end);
+            fragment = (start, end);
+        }
+
+        Ok(fragment)
+    }
+
+    fn atom(&mut self) -> Result<Fragment, RegexError> {
+        match self.chars.next() {
+            Some((position, '(')) => {
+                let fragment = self.alternation()?;
+                match self.chars.next() {
+                    Some((_, ')')) => Ok(fragment),
+                    _ => Err(RegexError::UnclosedGroup { position }),
+                }
+            }
+
+            Some((position, found)) if found == '*' || found == ')' => {
+                Err(RegexError::UnexpectedChar { position, found })
+            }
+
+            Some((_, c)) => {
+                let symbol = c.to_string();
+                if !self.symbols.contains(&c) {
+                    self.symbols.push(c);
+                    self.automata.push_symbol(&symbol);
+                }
+
+                let (start, end) = (self.new_state(), self.new_state());
+                self.automata
+                    .push_transition_from(start, Transition::new(&symbol, end));
+                Ok((start, end))
+            }
+
+            None => unreachable!("atoms are only parsed with input left"),
+        }
+    }
+}
+
+/// Regular expression built back from an automaton, see `Automata::to_regex`.
+/// The constructors apply the simplifications that keep the output readable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Expr {
+    Epsilon,
+    Symbol(String),
+    Concat(Vec<Expr>),
+    Alt(Vec<Expr>),
+    Star(Box<Expr>),
+}
+
+impl Expr {
+    pub(crate) fn alt(self, other: Expr) -> Expr {
+        let mut alternatives = match self {
+            Expr::Alt(alternatives) => alternatives,
+            expr => vec![expr],
+        };
+
+        let others = match other {
+            Expr::Alt(others) => others,
+            expr => vec![expr],
+        };
+
+        for expr in others {
+            if !alternatives.contains(&expr) {
+                alternatives.push(expr);
+            }
+        }
+
+        match alternatives.len() {
+            1 => alternatives.pop().unwrap(),
+            _ => Expr::Alt(alternatives),
+        }
+    }
+
+    pub(crate) fn concat(self, other: Expr) -> Expr {
+        match (self, other) {
+            (Expr::Epsilon, expr) | (expr, Expr::Epsilon) => expr,
+            (Expr::Concat(mut left), Expr::Concat(right)) => {
+                left.extend(right);
+                Expr::Concat(left)
+            }
+            (Expr::Concat(mut left), right) => {
+                left.push(right);
+                Expr::Concat(left)
+            }
+            (left, Expr::Concat(mut right)) => {
+                right.insert(0, left);
+                Expr::Concat(right)
+            }
+            (left, right) => Expr::Concat(vec![left, right]),
+        }
+    }
+
+    pub(crate) fn star(self) -> Expr {
+        match self {
+            Expr::Epsilon => Expr::Epsilon,
+            Expr::Star(expr) => Expr::Star(expr),
+            expr => Expr::Star(Box::new(expr)),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Epsilon => Ok(()),
+            Expr::Symbol(symbol) => write!(f, "{}", symbol),
+            Expr::Concat(parts) => {
+                for part in parts {
+                    match part {
+                        Expr::Alt(_) => write!(f, "({})", part)?,
+                        part => write!(f, "{}", part)?,
+                    }
+                }
+
+                Ok(())
+            }
+            Expr::Alt(alternatives) => {
+                for (index, alternative) in alternatives.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "|")?;
+                    }
+
+                    write!(f, "{}", alternative)?;
+                }
+
+                Ok(())
+            }
+            Expr::Star(expr) => match &**expr {
+                Expr::Symbol(symbol) if symbol.chars().count() == 1 => write!(f, "{}*", symbol),
+                expr => write!(f, "({})*", expr),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(word: &str) -> Vec<String> {
+        word.chars().map(|c| c.to_string()).collect()
+    }
+
+    fn accepts(automata: &Automata, word: &str) -> bool {
+        let word = chars(word);
+        let word: Vec<_> = word.iter().map(String::as_str).collect();
+        automata.accepts(&word)
+    }
+
+    #[test]
+    fn from_regex() {
+        let automata = super::from_regex("(a|b)*abb").unwrap();
+        let afd_automata = automata.to_deterministic();
+
+        for automata in &[&automata, &afd_automata] {
+            assert!(accepts(automata, "abb"));
+            assert!(accepts(automata, "aabb"));
+            assert!(accepts(automata, "babababb"));
+            assert!(!accepts(automata, "ab"));
+            assert!(!accepts(automata, "abba"));
+            assert!(!accepts(automata, ""));
+        }
+
+        assert_eq!(afd_automata.minimize().len(), 4);
+    }
+
+    #[test]
+    fn empty_alternatives_and_nested_stars() {
+        let automata = super::from_regex("a|").unwrap();
+        assert!(accepts(&automata, ""));
+        assert!(accepts(&automata, "a"));
+        assert!(!accepts(&automata, "aa"));
+
+        let automata = super::from_regex("(|b)c").unwrap();
+        assert!(accepts(&automata, "c"));
+        assert!(accepts(&automata, "bc"));
+        assert!(!accepts(&automata, "b"));
+
+        let automata = super::from_regex("(a*b*)**").unwrap().to_deterministic();
+        assert!(accepts(&automata, ""));
+        assert!(accepts(&automata, "abba"));
+        assert!(accepts(&automata, "bbbaaa"));
+
+        let automata = super::from_regex("").unwrap();
+        assert!(accepts(&automata, ""));
+        assert!(!accepts(&automata, "a"));
+    }
+
+    #[test]
+    fn regex_errors() {
+        assert_eq!(
+            super::from_regex("a)").unwrap_err(),
+            RegexError::UnexpectedChar {
+                position: 1,
+                found: ')'
+            }
+        );
+        assert_eq!(
+            super::from_regex("*a").unwrap_err(),
+            RegexError::UnexpectedChar {
+                position: 0,
+                found: '*'
+            }
+        );
+        assert_eq!(
+            super::from_regex("a(b|c").unwrap_err(),
+            RegexError::UnclosedGroup { position: 1 }
+        );
+    }
+}